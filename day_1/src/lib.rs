@@ -0,0 +1,180 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::num::ParseIntError;
+
+
+/// A spacecraft module, which needs fuel according to its mass.
+///
+/// ```
+/// use aoc_1::FuelModule;
+///
+/// let module = FuelModule { mass: 1969 };
+/// assert_eq!(module.basic_fuel(), 654);
+/// assert_eq!(module.total_fuel(), Some(966));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FuelModule {
+    pub mass: i64,
+}
+
+impl FuelModule {
+    /// The fuel for just this module's mass.
+    pub fn basic_fuel(&self) -> i64 {
+        basic_fuel(self.mass)
+    }
+
+    /// The fuel for this module and for all of that fuel, or `None` if that overflows.
+    pub fn total_fuel(&self) -> Option<i64> {
+        total_fuel(self.mass)
+    }
+}
+
+/// Why a line couldn't be read as a mass.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MassError {
+    /// Reading the line failed.
+    Io(io::ErrorKind),
+    /// The line isn't a whole number.
+    Parse(ParseIntError),
+}
+
+impl fmt::Display for MassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MassError::Io(kind) => write!(f, "reading it failed: {:?}", kind),
+            MassError::Parse(err) => write!(f, "not a mass: {}", err),
+        }
+    }
+}
+
+impl Error for MassError {}
+
+/// Reads one module per line, ignoring blank lines and surrounding whitespace.
+///
+/// Modules are parsed as they are asked for, so callers can stop early or skip bad lines.
+///
+/// ```
+/// use aoc_1::modules_from_reader;
+///
+/// let input = "12\n14\n\n1969\n".as_bytes();
+/// let fuel: i64 = modules_from_reader(input)
+///     .map(|module| module.unwrap().basic_fuel())
+///     .sum();
+/// assert_eq!(fuel, 2 + 2 + 654);
+/// ```
+pub fn modules_from_reader<R: BufRead>(reader: R) -> impl Iterator<Item = Result<FuelModule, MassError>> {
+    numbered_masses(reader).map(|(_, mass)| mass.map(|mass| FuelModule { mass }))
+}
+
+/// Reads one mass per line, ignoring blank lines and surrounding whitespace.
+///
+/// A line that isn't a mass, or can't be read, fails with its index, counting from 0.
+pub fn parse_masses<R: BufRead>(reader: R) -> Result<Vec<i64>, (usize, MassError)> {
+    numbered_masses(reader)
+        .map(|(index, mass)| mass.map_err(|err| (index, err)))
+        .collect()
+}
+
+/// Parses each line that isn't blank as a mass, alongside its index counting from 0.
+///
+/// A line that can't be read is passed on as `MassError::Io` rather than skipped.
+pub fn numbered_masses<R: BufRead>(reader: R) -> impl Iterator<Item = (usize, Result<i64, MassError>)> {
+    reader.lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(index, line)| (index, match line {
+            Ok(line) => line.trim().parse().map_err(MassError::Parse),
+            Err(err) => Err(MassError::Io(err.kind())),
+        }))
+}
+
+/// Sums the fuel for just the modules (part 1) and for the modules plus their fuel (part 2).
+///
+/// Fails with the index of the first module that makes either sum overflow.
+/// The masses are only walked once, so they can be streamed in.
+pub fn solve<I: IntoIterator<Item = i64>>(masses: I) -> Result<(i64, i64), usize> {
+    let mut module_sum: i64 = 0;
+    let mut total_sum: i64 = 0;
+    for (index, mass) in masses.into_iter().enumerate() {
+        module_sum = module_sum.checked_add(basic_fuel(mass)).ok_or(index)?;
+        total_sum = total_fuel(mass)
+            .and_then(|fuel| total_sum.checked_add(fuel))
+            .ok_or(index)?;
+    }
+    Ok((module_sum, total_sum))
+}
+
+// Fuel is (floor(mass / 3) - 2)
+pub fn basic_fuel(mass: i64) -> i64 {
+    mass.div_euclid(3) - 2
+}
+
+/// The fuel for `mass`, plus the fuel for that fuel, and so on until no more is needed,
+/// or `None` if that overflows.
+///
+/// Each round of fuel is under a third of the last, so the total stays under half
+/// of `mass` and one module can't actually overflow; the sums in `solve` can.
+pub fn total_fuel(mass: i64) -> Option<i64> {
+    let fuel_requirement: i64 = basic_fuel(mass);
+    if fuel_requirement <= 0 {
+        return Some(0);
+    }
+    fuel_requirement.checked_add(total_fuel(fuel_requirement)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_masses() {
+        assert_eq!(parse_masses("12\r\n\n  14 \n\t\n1969\n".as_bytes()), Ok(vec![12, 14, 1969]));
+
+        let (index, err) = parse_masses("12\n\n14x\n".as_bytes()).unwrap_err();
+        assert_eq!(index, 2);
+        assert!(matches!(err, MassError::Parse(_)));
+
+        // Lines that aren't UTF-8 can't be read at all
+        assert_eq!(parse_masses(&b"12\n\xff\n14\n"[..]), Err((1, MassError::Io(io::ErrorKind::InvalidData))));
+    }
+
+    #[test]
+    fn test_modules_from_reader() {
+        let modules: Vec<_> = modules_from_reader("12\n\nx\n14\n".as_bytes()).collect();
+
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules[0], Ok(FuelModule { mass: 12 }));
+        assert!(modules[1].is_err());
+        assert_eq!(modules[2], Ok(FuelModule { mass: 14 }));
+    }
+
+    #[test]
+    fn test_basic_fuel() {
+        assert_eq!(basic_fuel(12), 2);
+        assert_eq!(basic_fuel(14), 2);
+        assert_eq!(basic_fuel(1969), 654);
+        assert_eq!(basic_fuel(100756), 33583);
+    }
+
+    #[test]
+    fn test_total_fuel() {
+        assert_eq!(total_fuel(14), Some(2));
+        assert_eq!(total_fuel(1969), Some(966));
+        assert_eq!(total_fuel(100756), Some(50346));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let fuel = total_fuel(i64::MAX / 2).unwrap();
+        assert!(fuel > 0 && fuel < i64::MAX / 4);
+
+        // Each of these needs just under half of i64::MAX in total, so the third tips it over
+        assert_eq!(solve(vec![12, i64::MAX, i64::MAX, i64::MAX, 14]), Err(3));
+    }
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(vec![12, 14, 1969, 100756]), Ok((34241, 51316)));
+    }
+}
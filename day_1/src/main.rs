@@ -1,28 +1,125 @@
+use std::env;
+use std::fmt::Write;
 use std::io;
-use std::io::{BufReader, BufRead};
+use std::io::BufReader;
+use std::process;
+
+use aoc_1::{basic_fuel, numbered_masses, parse_masses, solve, total_fuel, MassError};
+
 
 fn main() {
+    let options = match options_from_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    };
+
     let input = io::stdin();
     let buffer = BufReader::new(input);
 
-    let mut total_required_fuel: i64 = 0;
-    for line in buffer.lines() {
+    let sums = if options.verbose {
+        // The table is aligned to the widest mass, so every mass has to be read first
+        let module_masses = match parse_masses(buffer) {
+            Ok(module_masses) => module_masses,
+            Err((index, err)) => exit_not_a_mass(index, err),
+        };
+        let sums = solve(module_masses.iter().copied());
+        if sums.is_ok() {
+            print!("{}", breakdown(&module_masses, options.part));
+        }
+        sums
+    } else {
+        let module_masses = numbered_masses(buffer)
+            .map(|(index, mass)| mass.unwrap_or_else(|err| exit_not_a_mass(index, err)));
+        solve(module_masses)
+    };
 
-        let line = line.expect("Failed to read line");
+    let (module_fuel, total_fuel) = match sums {
+        Ok(sums) => sums,
+        Err(index) => {
+            eprintln!("Fuel overflows at module {}", index + 1);
+            process::exit(1);
+        },
+    };
+    match options.part {
+        1 => println!("Modules require {} units of fuel", module_fuel),
+        _ => println!("Requires {} units of fuel", total_fuel),
+    }
+}
 
-        // Fuel is (floor(mass / 3) - 2)
-        let module_mass: i64 = line.parse().unwrap();
-        let module_required_fuel = get_fuel_requirement(module_mass);
+fn exit_not_a_mass(index: usize, err: MassError) -> ! {
+    match err {
+        MassError::Io(kind) => eprintln!("Failed to read line {}: {:?}", index + 1, kind),
+        MassError::Parse(err) => eprintln!("Line {} is not a mass: {}", index + 1, err),
+    }
+    process::exit(1);
+}
+
+/// What the command line asked for.
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    part: u8,
+    /// Whether to list the fuel for every module before the total.
+    verbose: bool,
+}
 
-        total_required_fuel += module_required_fuel;
+/// Reads `--part 1` or `--part 2`, defaulting to part 2, and `--verbose`.
+fn options_from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options { part: 2, verbose: false };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" => options.part = match args.next().as_deref() {
+                Some("1") => 1,
+                Some("2") => 2,
+                Some(other) => return Err(format!("Unknown part {:?}, expected 1 or 2", other)),
+                None => return Err("--part needs a value".to_string()),
+            },
+            "--verbose" => options.verbose = true,
+            other => return Err(format!("Unknown argument {:?}", other)),
+        }
     }
-    println!("Requires {} units of fuel", total_required_fuel);
+    Ok(options)
 }
 
-fn get_fuel_requirement(mass: i64) -> i64 {
-    let fuel_requirement: i64 = ((mass as f64 / 3.0).floor() as i64) - 2;
-    if fuel_requirement <= 0 {
-        return 0;
+/// One `mass=<n> fuel=<m>` line per module, with the masses right aligned.
+///
+/// The masses must already have been checked with `solve`, so none of them overflow.
+fn breakdown(masses: &[i64], part: u8) -> String {
+    let width = masses.iter().map(|mass| mass.to_string().len()).max().unwrap_or(0);
+
+    let mut table = String::new();
+    for mass in masses {
+        let fuel = match part {
+            1 => basic_fuel(*mass),
+            _ => total_fuel(*mass).expect("Fuel overflows"),
+        };
+        writeln!(table, "mass={:>width$} fuel={}", mass, fuel, width = width).unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_from_args() {
+        let args = |args: &[&str]| options_from_args(args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(args(&[]), Ok(Options { part: 2, verbose: false }));
+        assert_eq!(args(&["--part", "1"]), Ok(Options { part: 1, verbose: false }));
+        assert_eq!(args(&["--verbose", "--part", "2"]), Ok(Options { part: 2, verbose: true }));
+        assert!(args(&["--part", "3"]).is_err());
+        assert!(args(&["--part"]).is_err());
+    }
+
+    #[test]
+    fn test_breakdown() {
+        assert_eq!(breakdown(&[14, 1969, 100756], 2),
+            "mass=    14 fuel=2\nmass=  1969 fuel=966\nmass=100756 fuel=50346\n");
+        assert_eq!(breakdown(&[12, 1969], 1), "mass=  12 fuel=2\nmass=1969 fuel=654\n");
+        assert_eq!(breakdown(&[], 1), "");
     }
-    return fuel_requirement + get_fuel_requirement(fuel_requirement);
 }
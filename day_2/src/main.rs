@@ -1,166 +1,164 @@
+use std::env;
+use std::fmt::Write;
 use std::io;
-use std::io::Read;
+use std::process;
 
+use intcode::{find_reachable_halt, run_and_read, Address, ExecError, IntcodeProgram, ProgramRunner, Word};
+
+
+/// The output part 2 looks for, unless another is given on the command line.
+const DEFAULT_TARGET: Word = 19690720;
+
+const USAGE: &str = "Usage: aoc_2 [--dump] [TARGET] < PROGRAM";
 
 fn main() {
-    let mut stdin = io::stdin();
-    let mut program_string = String::new();
-    stdin.read_to_string(&mut program_string)
-        .expect("Failed to read file.");
+    let Options { target, dump_memory } = match options_from_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        },
+    };
 
     // Create program
-    let program = IntcodeProgram::from(&program_string);
+    let program = match IntcodeProgram::try_from_reader(io::stdin()) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("Failed to parse program: {}", err);
+            process::exit(1);
+        },
+    };
+    if find_reachable_halt(&program).is_none() {
+        eprintln!("Warning: no reachable halt instruction, the program may never finish");
+    }
+
+    match day2_part1(&program) {
+        Ok(answer) => println!("1202 program alarm: answer {}", answer),
+        Err(err) => {
+            eprintln!("Failed to run program: {}", err);
+            process::exit(1);
+        },
+    }
 
+    let mut runner = ProgramRunner::new(program);
     for noun in 0..100 {
         for verb in 0..100 {
-            if try_with(noun, verb, &program) {
-                println!("{} and {}: answer {}", noun, verb, (100 * noun + verb));
-                return;
+            match try_with(noun, verb, target, &mut runner) {
+                Ok(true) => {
+                    println!("{} and {}: answer {}", noun, verb, (100 * noun + verb));
+                    if dump_memory {
+                        print!("{}", build_summary(runner.original(), runner.memory()));
+                    }
+                    return;
+                },
+                Ok(false) => (),
+                Err(err) => {
+                    eprintln!("Failed to run program: {}", err);
+                    process::exit(1);
+                },
             }
         }
     }
-    println!("No answer!");
+    eprintln!("No noun and verb below 100 give {}", target);
+    process::exit(1);
 }
 
-fn try_with(noun: i64, verb: i64, program: &IntcodeProgram) -> bool {
-    let mut program_copy = program.clone();
-    restore_gravity_assist(noun, verb, &mut program_copy);
-
-    let mut cpu = Cpu::new();
-    cpu.execute(&mut program_copy);
-
-    return program_copy.read_at(0) == 19690720;
+/// What the command line asked for.
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    /// The output part 2 looks for.
+    target: Word,
+    /// Whether to list the cells the part 2 answer changed.
+    dump_memory: bool,
 }
 
-fn restore_gravity_assist(noun: i64, verb: i64, program: &mut dyn Memory) {
-    program.write_at(noun, 1);
-    program.write_at(verb, 2);
+/// Reads `--dump` and an optional target, defaulting to `DEFAULT_TARGET`.
+fn options_from_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options { target: DEFAULT_TARGET, dump_memory: false };
+    let mut target_given = false;
+    for arg in args {
+        match arg.as_str() {
+            "--dump" => options.dump_memory = true,
+            other if other.starts_with("--") => return Err(format!("Unknown argument {:?}", other)),
+            other if target_given => return Err(format!("Unexpected argument {:?}, the target is already given", other)),
+            other => {
+                options.target = other.parse().map_err(|_| format!("Target is not an integer: {:?}", other))?;
+                target_given = true;
+            },
+        }
+    }
+    Ok(options)
 }
 
-type Address = usize;
-type Value = i64;
-// struct Address(u64);
-
-trait Memory {
-    fn read_at(&self, address: Address) -> i64;
-    fn write_at(&mut self, value: i64, address: Address);
+/// Restores the "1202 program alarm" state and reports what the program leaves at address 0.
+fn day2_part1(program: &IntcodeProgram) -> Result<Word, ExecError> {
+    run_and_read(program, &[(Address(1), 12), (Address(2), 2)], Address(0))
 }
 
-enum Instruction {
-    Add(Address, Address, Address),
-    Mult(Address, Address, Address),
-    Halt(),
-}
+/// Runs the program with the given noun and verb, leaving its final memory in `runner`,
+/// and says whether it left `target` at address 0.
+fn try_with(noun: Word, verb: Word, target: Word, runner: &mut ProgramRunner) -> Result<bool, ExecError> {
+    runner.run(&[(Address(1), noun), (Address(2), verb)])?;
 
-struct Cpu {
-    instruction_ptr: Address,
+    Ok(runner.memory().at(Address(0)) == target)
 }
 
-impl Cpu {
-    fn new() -> Cpu {
-        Cpu {
-            instruction_ptr: 0
-        }
-    }
-
-    fn execute(&mut self, memory: &mut dyn Memory) {
-
-        loop {
-            let instruction = self.get_next_instruction(memory);
-
-            match instruction {
-                Instruction::Add(param_0, param_1, res) => {
-                    eprintln!("exec ADD  @{:3}: &{:3} &{:3} ->&{:3} ({:3}+{:3})",
-                        self.instruction_ptr-4, param_0, param_1, res, memory.read_at(param_0),
-                        memory.read_at(param_1));
-                    memory.write_at(memory.read_at(param_0) + memory.read_at(param_1), res);
-                },
-                Instruction::Mult(param_0, param_1, res) => {
-                    eprintln!("exec MULT @{:3}: &{:3} &{:3} ->&{:3} ({:3}*{:3})",
-                        self.instruction_ptr-4, param_0, param_1, res, memory.read_at(param_0),
-                        memory.read_at(param_1));
-                    memory.write_at(memory.read_at(param_0) * memory.read_at(param_1), res);
-                },
-                Instruction::Halt() => {
-                    eprintln!("exec HALT @{:3}:", self.instruction_ptr-4);
-                    return
-                },
-            }
-        }
-    }
-
-    fn get_next_instruction(&mut self, program: &dyn Memory) -> Instruction {
-        let instruction = self.instruction_at(self.instruction_ptr, program);
-        self.instruction_ptr += 4;
-        instruction
-    }
+/// Lists every cell the run changed, one `@address: before -> after` per line.
+fn build_summary(original: &IntcodeProgram, final_memory: &IntcodeProgram) -> String {
+    let changes = original.diff(final_memory);
 
-    fn instruction_at(&self, address: Address, program: &dyn Memory) -> Instruction {
-        let opcode = program.read_at(address);
-        match opcode {
-            1 => {
-                let param_addr_0 = program.read_at(address + 1) as Address;
-                let param_addr_1 = program.read_at(address + 2) as Address;
-                let result_addr = program.read_at(address + 3) as Address;
-                Instruction::Add(param_addr_0, param_addr_1, result_addr)
-            },
-            2 => {
-                let param_addr_0 = program.read_at(address + 1) as Address;
-                let param_addr_1 = program.read_at(address + 2) as Address;
-                let result_addr = program.read_at(address + 3) as Address;
-                Instruction::Mult(param_addr_0, param_addr_1, result_addr)
-            },
-            99 => Instruction::Halt(),
-            _ => Instruction::Halt(), // this should error instead
-        }
+    let mut summary = format!("{} cells changed:\n", changes.len());
+    for (address, before, after) in changes {
+        writeln!(summary, "  @{:4}: {} -> {}", address, before, after).unwrap();
     }
+    summary
 }
 
-struct IntcodeProgram {
-    raw_program: Vec<Value>,
-}
-
-impl Memory for IntcodeProgram {
-    fn read_at(&self, address: Address) -> i64 {
-        self.raw_program[address]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_from_args() {
+        let args = |args: &[&str]| options_from_args(args.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(args(&[]), Ok(Options { target: DEFAULT_TARGET, dump_memory: false }));
+        assert_eq!(args(&["--dump", "42"]), Ok(Options { target: 42, dump_memory: true }));
+        assert_eq!(args(&["-7"]), Ok(Options { target: -7, dump_memory: false }));
+        assert!(args(&["--dmp"]).is_err());
+        assert!(args(&["--target=42"]).is_err());
+        assert!(args(&["x"]).is_err());
+        assert!(args(&["1", "2"]).is_err());
     }
 
-    fn write_at(&mut self, value: i64, address: Address) {
-        self.raw_program[address] = value;
-    }
-}
+    #[test]
+    fn test_day2_part1() {
+        // 1 + 2 from the patched noun and verb addresses
+        let program = IntcodeProgram::from("1,0,0,0,99,0,0,0,0,0,0,0,1,0,0,0,0,0");
+        assert_eq!(day2_part1(&program), Ok(3));
 
-impl IntcodeProgram {
-    fn from(s: &String) -> IntcodeProgram {
-        let program_vec = s.trim()
-            .split(",")
-            .map(|code| code.parse().expect("Failed to parse code"))
-            .collect();
-
-        IntcodeProgram{
-            raw_program: program_vec,
-        }
+        let program = IntcodeProgram::from(include_str!("../input"));
+        assert_eq!(day2_part1(&program), Ok(4090689));
     }
 
-    fn from_vec(v: Vec<Value>) -> IntcodeProgram {
-        IntcodeProgram{
-            raw_program: v,
-        }
-    }
+    #[test]
+    fn test_try_with() {
+        // Adds the words at the noun and verb addresses
+        let mut runner = ProgramRunner::new(IntcodeProgram::from("1,0,0,0,99,7,8"));
+        assert_eq!(try_with(5, 6, 15, &mut runner), Ok(true));
+        assert_eq!(try_with(5, 5, 15, &mut runner), Ok(false));
+        assert_eq!(try_with(5, 5, 14, &mut runner), Ok(true));
 
-    fn len(&self) -> usize {
-        self.raw_program.len()
+        let mut runner = ProgramRunner::new(IntcodeProgram::from(include_str!("../input")));
+        assert_eq!(try_with(77, 33, DEFAULT_TARGET, &mut runner), Ok(true));
     }
-}
 
-impl Clone for IntcodeProgram {
-    fn clone(&self) -> Self {
-        let mut clone = Self {
-            raw_program: Vec::with_capacity(self.raw_program.len()),
-        };
-        for value in self.raw_program.iter() {
-            clone.raw_program.push(*value);
-        }
-        clone
+    #[test]
+    fn test_build_summary() {
+        let mut runner = ProgramRunner::new(IntcodeProgram::from("1,0,0,0,99,7,8"));
+        assert_eq!(try_with(5, 6, DEFAULT_TARGET, &mut runner), Ok(false));
+
+        assert_eq!(build_summary(runner.original(), runner.memory()),
+            "3 cells changed:\n  @   0: 1 -> 15\n  @   1: 0 -> 5\n  @   2: 0 -> 6\n");
     }
 }
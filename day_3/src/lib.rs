@@ -0,0 +1,1783 @@
+use std::iter::Iterator;
+use std::cmp::{Eq, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The most cells `render` will draw before only reporting the grid's size.
+const RENDER_CELL_LIMIT: u64 = 1 << 20;
+
+/// Represents direction on a compass, including the four diagonals.
+#[derive(Copy, Clone, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+/// Represents a range of values from [lower, upper).
+///
+/// An `Edge` converts to the `Interval` of every coordinate it touches along its
+/// axis, endpoints included, so its `upper` is one past its furthest coordinate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Interval {
+    lower: i64,
+    upper: i64,
+}
+
+/// Represents a point in 2 dimensions.
+#[derive(Copy, Clone, Debug, Eq)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Represents an intersection of two `Wires`.
+#[derive(Debug, Eq)]
+pub struct Intersection {
+    /// The sum of the distances along the two wires to reach this intersection.
+    pub distance: u64,
+    pub point: Point,
+    /// Steps along the first wire, the one `get_intersections` was called on.
+    steps_a: u64,
+    /// Steps along the second wire.
+    steps_b: u64,
+}
+
+/// Represents an intersection of two `Wires` with the steps taken along each.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DetailedIntersection {
+    pub point: Point,
+    /// Steps along the wire `detailed_intersections` was called on.
+    pub steps_self: u64,
+    /// Steps along the other wire.
+    pub steps_other: u64,
+    /// Indices of the crossing edges, on this wire then the other.
+    pub edges: (usize, usize),
+}
+
+/// Represents an edge of a `Wire`: an origin, direction, and magnitude.
+#[derive(Copy, Clone, Debug)]
+pub struct Edge {
+    pub direction: Direction,
+    pub magnitude: i64,
+    pub origin: Point,
+}
+
+/// Represents where two `Edges` cross, and whether that is at a corner of either.
+#[derive(Copy, Clone, Debug)]
+pub struct Crossing {
+    pub point: Point,
+    pub on_self_endpoint: bool,
+    pub on_other_endpoint: bool,
+}
+
+/// Represents an `Intersection` found among many wires, tagged with the two that produced it.
+#[derive(Debug)]
+pub struct TaggedIntersection {
+    /// Indices of the two crossing wires, lower index first.
+    pub wires: (usize, usize),
+    pub intersection: Intersection,
+}
+
+/// Represents a problem with the shape of a `Wire`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WireError {
+    /// The edge at `index` doesn't start where the edge before it ends.
+    Disconnected { index: usize, expected: Point, found: Point },
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::Disconnected { index, expected, found } => write!(f,
+                "Edge {} starts at ({}, {}) instead of ({}, {})",
+                index, found.x, found.y, expected.x, expected.y),
+        }
+    }
+}
+
+impl Error for WireError {}
+
+/// Represents an edge in a wire's text that couldn't be parsed, carrying the edge as written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WireParseError {
+    /// The edge doesn't start with a known direction.
+    UnknownDirection { token: String },
+    /// There was nothing between two commas.
+    EmptyToken { token: String },
+    /// The direction isn't followed by a whole number of steps.
+    BadMagnitude { token: String },
+}
+
+impl fmt::Display for WireParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireParseError::UnknownDirection { token } => write!(f,
+                "Unrecognized direction! Edge {:?} must start with U, D, L, R, UL, UR, DL or DR", token),
+            WireParseError::EmptyToken { token } => write!(f, "Empty edge {:?}", token),
+            WireParseError::BadMagnitude { token } => write!(f, "Failed to parse magnitude of edge {:?}", token),
+        }
+    }
+}
+
+impl Error for WireParseError {}
+
+pub struct Wire {
+    edges: Vec<Edge>,
+    /// The steps along the wire to reach the origin of each edge.
+    cumulative_lengths: Vec<u64>,
+}
+
+impl Point {
+    /// Distance from the origin, uses Manhattan distance.
+    pub fn distance_from_origin(&self) -> u64 {
+        self.x.unsigned_abs() + self.y.unsigned_abs()
+    }
+
+    /// Distance from the other Point, uses Manhattan distance.
+    pub fn distance_from(&self, other: &Self) -> u64 {
+        let x_distance = (self.x - other.x).unsigned_abs();
+        let y_distance = (self.y - other.y).unsigned_abs();
+        x_distance + y_distance
+    }
+
+    /// Squared Euclidean distance from the origin, kept in integers to stay exact.
+    ///
+    /// Widened to `u128`, where even two squares of `i64::MIN` can't overflow.
+    pub fn euclidean_sq_from_origin(&self) -> u128 {
+        (self.x.unsigned_abs() as u128).pow(2) + (self.y.unsigned_abs() as u128).pow(2)
+    }
+
+    /// Positive if going from `self` to `a` to `b` turns counter-clockwise, zero if they're in a line.
+    fn cross(&self, a: &Point, b: &Point) -> i64 {
+        (a.x - self.x) * (b.y - self.y) - (a.y - self.y) * (b.x - self.x)
+    }
+}
+
+/// Orders points by Manhattan distance from the origin, so the closest sorts first.
+/// Points at the same distance are ordered by `x` and then `y`, keeping this
+/// consistent with equality.
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_from_origin().cmp(&other.distance_from_origin())
+            .then(self.x.cmp(&other.x))
+            .then(self.y.cmp(&other.y))
+    }
+}
+
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Point {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
+impl Intersection {
+    fn new(steps_a: u64, steps_b: u64, point: Point) -> Self {
+        Self {
+            distance: steps_a + steps_b,
+            point,
+            steps_a,
+            steps_b,
+        }
+    }
+
+    /// Steps along the first wire to reach this intersection.
+    pub fn steps_a(&self) -> u64 {
+        self.steps_a
+    }
+
+    /// Steps along the second wire to reach this intersection.
+    pub fn steps_b(&self) -> u64 {
+        self.steps_b
+    }
+}
+
+/// Orders intersections by `distance`, the fewest combined steps first, then by `point`
+/// to agree with equality. How the steps split between the wires isn't compared.
+impl Ord for Intersection {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.cmp(&other.distance).then(self.point.cmp(&other.point))
+    }
+}
+
+impl PartialOrd for Intersection {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Intersection {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance && self.point == other.point
+    }
+}
+
+impl Direction {
+    /// The change in (x, y) from taking a single step in this direction.
+    fn step(&self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
+
+    /// Whether a step in this direction moves along both axes at once.
+    fn is_diagonal(&self) -> bool {
+        let (x_step, y_step) = self.step();
+        x_step != 0 && y_step != 0
+    }
+}
+
+impl Interval {
+    fn new(lower: i64, upper: i64) -> Self {
+        assert!(lower < upper);
+        Self {
+            lower,
+            upper,
+        }
+    }
+
+    /// Includes `lower` but not `upper`.
+    fn contains(&self, value: i64) -> bool {
+        self.lower <= value && value < self.upper
+    }
+
+    /// Whether any value is contained in both intervals.
+    fn overlaps(&self, other: &Self) -> bool {
+        self.contains(other.lower) || other.contains(self.lower)
+    }
+}
+
+impl Edge {
+    /// Where this `Edge` ends, `magnitude` steps from its origin. A diagonal step
+    /// moves one along each axis.
+    pub fn get_endpoint(&self) -> Point {
+        let (x_step, y_step) = self.direction.step();
+        Point {
+            x: self.origin.x + x_step * self.magnitude,
+            y: self.origin.y + y_step * self.magnitude,
+        }
+    }
+
+    /// Steps along this `Edge` from its origin to `point`, which must lie on it.
+    pub fn steps_to(&self, point: &Point) -> u64 {
+        // A diagonal step covers one along each axis, so this is the larger of the two
+        (point.x - self.origin.x).unsigned_abs().max((point.y - self.origin.y).unsigned_abs())
+    }
+
+    /// The lattice points visited along this `Edge`, excluding its origin.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let origin = self.origin;
+        let (x_step, y_step) = self.direction.step();
+        (1..=self.magnitude).map(move |steps| Point {
+            x: origin.x + x_step * steps,
+            y: origin.y + y_step * steps,
+        })
+    }
+
+    /// Transforms this `Edge` to a directionless `Interval`, covering the
+    /// `magnitude + 1` coordinates from whichever end is lower.
+    ///
+    /// Diagonal edges visit each x once, so they cover their x coordinates.
+    fn as_interval(&self) -> Interval {
+        let endpoint = self.get_endpoint();
+        match self.direction {
+            Direction::Up => Interval::new(self.origin.y, endpoint.y + 1),
+            Direction::Down => Interval::new(endpoint.y, self.origin.y + 1),
+            Direction::Left => Interval::new(endpoint.x, self.origin.x + 1),
+            Direction::Right => Interval::new(self.origin.x, endpoint.x + 1),
+            Direction::UpLeft | Direction::UpRight | Direction::DownLeft | Direction::DownRight => {
+                let (lower, upper) = self.span();
+                Interval::new(lower, upper + 1)
+            },
+        }
+    }
+
+    fn parallel(&self, other: &Self) -> bool {
+        let (x_step, y_step) = self.direction.step();
+        let (other_x_step, other_y_step) = other.direction.step();
+        x_step * other_y_step == y_step * other_x_step
+    }
+
+    /// Whether both edges lie along the same line.
+    fn colinear(&self, other: &Self) -> bool {
+        // Parallel edges share a line when the step between their origins runs along it too
+        let (x_step, y_step) = self.direction.step();
+        self.parallel(other)
+            && x_step * (other.origin.y - self.origin.y) == y_step * (other.origin.x - self.origin.x)
+    }
+
+    /// Whether the edges run along each other for at least one point.
+    ///
+    /// Like `is_crossing`, edges that only touch at an endpoint count.
+    pub fn is_overlapping(&self, other: &Self) -> bool {
+        if !(self.colinear(other) && self.parallel(other)) {
+            false
+        } else {
+            let my_interval = self.as_interval();
+            let other_interval = other.as_interval();
+
+            my_interval.overlaps(&other_interval)
+        }
+    }
+
+    /// Whether perpendicular edges share a point, including at either endpoint.
+    ///
+    /// Diagonal edges only count when they share a grid point, not where they pass
+    /// between points.
+    pub fn is_crossing(&self, other: &Self) -> bool {
+        if self.parallel(other) {
+            return false;
+        }
+        if other.direction.is_diagonal() {
+            return self.segment_intersection(other).is_some();
+        }
+
+        let my_interval = self.as_interval();
+        let other_interval = other.as_interval();
+
+        match self.direction {
+            Direction::Up | Direction::Down => {
+                // If we are between their origin and endpoint wrt X
+                // If we are surrounding their origin and endpoint wrt Y
+                other_interval.contains(self.origin.x) && my_interval.contains(other.origin.y)
+            },
+            Direction::Left | Direction::Right => {
+                // If we are between their origin and endpoint wrt Y
+                // If we are surrounding their origin and endpoint wrt X
+                other_interval.contains(self.origin.y) && my_interval.contains(other.origin.x)
+            },
+            Direction::UpLeft | Direction::UpRight | Direction::DownLeft | Direction::DownRight => {
+                self.segment_intersection(other).is_some()
+            },
+        }
+    }
+
+    pub fn get_intersection(&self, other: &Self) -> Option<Point> {
+        self.get_crossing(other).map(|crossing| crossing.point)
+    }
+
+    /// Like `get_intersection`, but also reports whether the point is a corner of either edge.
+    pub fn get_crossing(&self, other: &Self) -> Option<Crossing> {
+        if !self.is_crossing(other) {
+            return None;
+        }
+
+        let point = match (self.direction, other.direction) {
+            (Direction::Up | Direction::Down, Direction::Left | Direction::Right) => Point { x: self.origin.x, y: other.origin.y, },
+            (Direction::Left | Direction::Right, Direction::Up | Direction::Down) => Point { x: other.origin.x, y: self.origin.y, },
+            _ => self.segment_intersection(other)?,
+        };
+
+        Some(Crossing {
+            point,
+            on_self_endpoint: self.has_endpoint(&point),
+            on_other_endpoint: other.has_endpoint(&point),
+        })
+    }
+
+    /// Where colinear edges that run along each other meet first, as the point they
+    /// share with the fewest steps from both origins combined.
+    ///
+    /// The origin is only returned when it is the one point they share. Points tied
+    /// on steps are settled by whichever this `Edge` reaches first.
+    pub fn get_overlap(&self, other: &Self) -> Option<Point> {
+        let (first, last) = self.overlap_ends(other)?;
+
+        // Steps from either origin only ever grow or shrink along the shared
+        // stretch, so the best point is at one of its ends
+        [first, last].iter().copied()
+            .min_by_key(|point| (self.steps_to(point) + other.steps_to(point), self.steps_to(point)))
+    }
+
+    /// The ends of the stretch colinear edges share, in the order this `Edge` walks
+    /// them.
+    ///
+    /// An end at the origin is moved one step in, so the origin is only an end when
+    /// it is the one point they share.
+    fn overlap_ends(&self, other: &Self) -> Option<(Point, Point)> {
+        if !self.is_overlapping(other) {
+            return None;
+        }
+
+        let (my_lower, my_upper) = self.span();
+        let (other_lower, other_upper) = other.span();
+        let lower = my_lower.max(other_lower);
+        let upper = my_upper.min(other_upper);
+        if lower > upper {
+            return None;
+        }
+
+        let (x_step, y_step) = self.direction.step();
+        let at = |along: i64| match self.direction {
+            Direction::Up | Direction::Down => Point { x: self.origin.x, y: along },
+            Direction::Left | Direction::Right => Point { x: along, y: self.origin.y },
+            Direction::UpLeft | Direction::UpRight | Direction::DownLeft | Direction::DownRight => {
+                Point { x: along, y: self.origin.y + (along - self.origin.x) * x_step * y_step }
+            },
+        };
+        let (mut first, mut last) = (at(lower), at(upper));
+        if self.steps_to(&first) > self.steps_to(&last) {
+            (first, last) = (last, first);
+        }
+
+        let origin = Point { x: 0, y: 0 };
+        if first != last && first == origin {
+            first = Point { x: first.x + x_step, y: first.y + y_step };
+        } else if first != last && last == origin {
+            last = Point { x: last.x - x_step, y: last.y - y_step };
+        }
+        Some((first, last))
+    }
+
+    /// The lowest and highest coordinate this `Edge` covers along its axis, or
+    /// along x for a diagonal.
+    fn span(&self) -> (i64, i64) {
+        let endpoint = self.get_endpoint();
+        match self.direction {
+            Direction::Up | Direction::Down => (self.origin.y.min(endpoint.y), self.origin.y.max(endpoint.y)),
+            _ => (self.origin.x.min(endpoint.x), self.origin.x.max(endpoint.x)),
+        }
+    }
+
+    /// The grid point where non-parallel edges meet, in any directions.
+    ///
+    /// Solves `origin + t * step = other.origin + u * other.step` for whole numbers of
+    /// steps within both edges, so it also works when neither edge is axis aligned.
+    fn segment_intersection(&self, other: &Self) -> Option<Point> {
+        let (x_step, y_step) = self.direction.step();
+        let (other_x_step, other_y_step) = other.direction.step();
+        let denominator = x_step * other_y_step - y_step * other_x_step;
+        if denominator == 0 {
+            return None;
+        }
+
+        let x_gap = other.origin.x - self.origin.x;
+        let y_gap = other.origin.y - self.origin.y;
+        let my_numerator = x_gap * other_y_step - y_gap * other_x_step;
+        let other_numerator = x_gap * y_step - y_gap * x_step;
+        // Perpendicular diagonals can pass between grid points without sharing one
+        if my_numerator % denominator != 0 || other_numerator % denominator != 0 {
+            return None;
+        }
+
+        let my_steps = my_numerator / denominator;
+        let other_steps = other_numerator / denominator;
+        if !(0..=self.magnitude).contains(&my_steps) || !(0..=other.magnitude).contains(&other_steps) {
+            return None;
+        }
+
+        Some(Point {
+            x: self.origin.x + x_step * my_steps,
+            y: self.origin.y + y_step * my_steps,
+        })
+    }
+
+    /// Whether `point` is the origin or endpoint of this `Edge`.
+    fn has_endpoint(&self, point: &Point) -> bool {
+        *point == self.origin || *point == self.get_endpoint()
+    }
+}
+
+impl Wire {
+    /// Like `try_from_string`, but panics on a malformed edge.
+    pub fn from_string(string: &str) -> Self {
+        Wire::try_from_string(string).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Parses comma separated edges like `R8` or `UL30`, starting from the origin.
+    pub fn try_from_string(string: &str) -> Result<Self, WireParseError> {
+        let mut current_position = Point {
+            x: 0,
+            y: 0,
+        };
+        let mut edges = Vec::<Edge>::new();
+        for edge_str in string.trim().split(',') {
+            let edge = Wire::create_edge(edge_str, &current_position)?;
+            current_position = edge.get_endpoint();
+            edges.push(edge);
+        }
+
+        Ok(Wire::from_edges(edges))
+    }
+
+    fn from_edges(edges: Vec<Edge>) -> Self {
+        let cumulative_lengths = Wire::accumulate_lengths(&edges);
+
+        let wire = Wire {
+            edges,
+            cumulative_lengths,
+        };
+        debug_assert_eq!(wire.validate_connectivity(), Ok(()));
+        wire
+    }
+
+    /// Checks that every edge starts where the edge before it ends.
+    pub fn validate_connectivity(&self) -> Result<(), WireError> {
+        for (index, pair) in self.edges.windows(2).enumerate() {
+            let expected = pair[0].get_endpoint();
+            if pair[1].origin != expected {
+                return Err(WireError::Disconnected {
+                    index: index + 1,
+                    expected,
+                    found: pair[1].origin,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn create_edge(vector_str: &str, current_pos: &Point) -> Result<Edge, WireParseError> {
+        if vector_str.trim().is_empty() {
+            return Err(WireParseError::EmptyToken { token: vector_str.to_string() });
+        }
+        // Hand written wires often have spaces after the commas
+        let vector_str = vector_str.trim();
+
+        // Diagonals take two letters, like UL30
+        let split = match vector_str.get(..2) {
+            Some("UL" | "UR" | "DL" | "DR") => 2,
+            _ => vector_str.chars().next().map_or(0, char::len_utf8),
+        };
+        let direction = &vector_str[..split];
+        let direction = match direction {
+            "U" => Direction::Up,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            "UL" => Direction::UpLeft,
+            "UR" => Direction::UpRight,
+            "DL" => Direction::DownLeft,
+            "DR" => Direction::DownRight,
+            _ => return Err(WireParseError::UnknownDirection { token: vector_str.to_string() }),
+        };
+
+        // A wire can't step backwards, so negative magnitudes are as bad as missing ones
+        let magnitude: i64 = vector_str[split..].parse()
+            .ok()
+            .filter(|magnitude| *magnitude >= 0)
+            .ok_or_else(|| WireParseError::BadMagnitude { token: vector_str.to_string() })?;
+
+        Ok(Edge {
+            direction,
+            magnitude,
+            origin: *current_pos,
+        })
+    }
+
+    /// Builds the running step count at the start of each edge.
+    fn accumulate_lengths(edges: &[Edge]) -> Vec<u64> {
+        let mut cumulative_lengths = Vec::<u64>::with_capacity(edges.len());
+        let mut length: u64 = 0;
+        for edge in edges {
+            cumulative_lengths.push(length);
+            length += edge.magnitude as u64;
+        }
+        cumulative_lengths
+    }
+
+    /// Number of steps along the wire to reach the origin of the edge at `index`.
+    fn steps_to_edge(&self, index: usize) -> u64 {
+        self.cumulative_lengths[index]
+    }
+
+    /// Every lattice point the wire visits in order, starting from its origin.
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.edges.first()
+            .map(|edge| edge.origin)
+            .into_iter()
+            .chain(self.edges.iter().flat_map(|edge| edge.points()))
+    }
+
+    pub fn iter(&self) -> WireIter<'_> {
+        WireIter {
+            data: self,
+            index: 0,
+        }
+    }
+
+    /// The crossing nearest the origin by Manhattan distance, ignoring the origin itself.
+    pub fn closest_by_manhattan(&self, other: &Self) -> Option<Intersection> {
+        self.get_intersections(other)
+            .into_iter()
+            .min_by_key(|intersection| intersection.point)
+    }
+
+    /// The crossing reached in the fewest steps along both wires combined, ignoring the origin.
+    pub fn fewest_steps(&self, other: &Self) -> Option<Intersection> {
+        self.get_intersections(other)
+            .into_iter()
+            .min()
+    }
+
+    /// The crossing nearest the origin as the crow flies, ignoring the origin itself.
+    ///
+    /// Crossings at the same Euclidean distance are ordered by Manhattan distance.
+    pub fn closest_by_euclidean_sq(&self, other: &Self) -> Option<Intersection> {
+        self.get_intersections(other)
+            .into_iter()
+            .min_by(|a, b| {
+                a.point.euclidean_sq_from_origin().cmp(&b.point.euclidean_sq_from_origin())
+                    .then(a.point.cmp(&b.point))
+            })
+    }
+
+    /// The convex hull of the points where the wires cross, ignoring the origin.
+    ///
+    /// Corners run counter-clockwise from the one with the lowest x, then y, and
+    /// points along a hull edge are left out. With fewer than three distinct
+    /// crossings there is no hull, so those crossings are returned in that order.
+    pub fn intersection_hull(&self, other: &Self) -> Vec<Point> {
+        let mut points: Vec<Point> = self.get_intersections(other)
+            .into_iter()
+            .map(|intersection| intersection.point)
+            .collect();
+        // Andrew's monotone chain sweeps left to right, not by distance from the origin
+        points.sort_by_key(|point| (point.x, point.y));
+        points.dedup();
+        if points.len() < 3 {
+            return points;
+        }
+
+        let mut hull = Vec::<Point>::with_capacity(points.len() + 1);
+        // Lower hull left to right, then upper hull right to left
+        for pass in [points.clone(), points.into_iter().rev().collect()] {
+            let start = hull.len();
+            for point in pass {
+                while hull.len() >= start + 2 && hull[hull.len() - 2].cross(&hull[hull.len() - 1], &point) <= 0 {
+                    hull.pop();
+                }
+                hull.push(point);
+            }
+            // Each pass ends where the next one starts
+            hull.pop();
+        }
+        hull
+    }
+
+    /// The area inside the wire, if it ends back where it started.
+    ///
+    /// Uses the shoelace formula over the corners, so a wire that crosses itself
+    /// counts the loops it winds in opposite directions against each other.
+    pub fn enclosed_area(&self) -> Option<u64> {
+        let start = self.edges.first()?.origin;
+        if self.edges.last()?.get_endpoint() != start {
+            return None;
+        }
+
+        let twice_area: i64 = self.edges.iter()
+            .map(|edge| {
+                let (from, to) = (edge.origin, edge.get_endpoint());
+                from.x * to.y - to.x * from.y
+            })
+            .sum();
+        Some(twice_area.unsigned_abs() / 2)
+    }
+
+    /// The smallest Manhattan distance between a point on this wire and one on `other`,
+    /// which is 0 if they cross.
+    ///
+    /// Both wires start at the origin, so it is left out of both, and a wire with
+    /// no other points is infinitely far away, giving `u64::MAX`. Every pair of
+    /// points is compared, so this is only suited to short wires.
+    pub fn minimum_gap(&self, other: &Self) -> u64 {
+        let origin = Point { x: 0, y: 0 };
+        let other_points: Vec<Point> = other.points().filter(|point| *point != origin).collect();
+
+        self.points()
+            .filter(|point| *point != origin)
+            .flat_map(|point| other_points.iter().map(move |other_point| point.distance_from(other_point)))
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Every point where the wires cross, except the origin they both start from.
+    pub fn get_intersections(&self, other: &Self) -> Vec<Intersection> {
+        let origin = Point { x: 0, y: 0 };
+        self.detailed_intersections(other)
+            .into_iter()
+            .filter(|detailed| detailed.point != origin)
+            .map(|detailed| Intersection::new(detailed.steps_self, detailed.steps_other, detailed.point))
+            .collect()
+    }
+
+    /// Every point where the wires cross, with the steps along each wire to get
+    /// there and which of their edges cross.
+    ///
+    /// Edges running along each other meet once, at the shared point with the
+    /// fewest combined steps, leaving out the origin when they share any other.
+    pub fn detailed_intersections(&self, other: &Self) -> Vec<DetailedIntersection> {
+        let mut result = Vec::<DetailedIntersection>::new();
+
+        for (my_index, edge) in self.iter().enumerate() {
+            for (other_index, other_edge) in other.iter().enumerate() {
+                match edge.get_intersection(&other_edge).or_else(|| edge.get_overlap(&other_edge)) {
+                    None => (),
+                    Some(intersection) => {
+                        // Find the partial distance from these edges
+                        let mut my_partial_distance = edge.steps_to(&intersection);
+                        my_partial_distance += self.steps_to_edge(my_index);
+                        let mut other_partial_distance = other_edge.steps_to(&intersection);
+                        other_partial_distance += other.steps_to_edge(other_index);
+
+                        result.push(DetailedIntersection {
+                            point: intersection,
+                            steps_self: my_partial_distance,
+                            steps_other: other_partial_distance,
+                            edges: (my_index, other_index),
+                        });
+                    },
+                }
+            }
+        }
+
+        result
+    }
+
+    /// For each edge, the first point it crosses `other` at when walked in its own
+    /// direction, ignoring the origin.
+    pub fn first_crossings_per_edge(&self, other: &Self) -> Vec<Option<Point>> {
+        let origin = Point { x: 0, y: 0 };
+        let mut firsts: Vec<Option<Point>> = vec![None; self.edges.len()];
+        for detailed in self.detailed_intersections(other) {
+            if detailed.point == origin {
+                continue;
+            }
+
+            let edge = &self.edges[detailed.edges.0];
+            // An overlap is reached where this edge first runs into it, not where it
+            // is best for both wires
+            let point = edge.overlap_ends(&other.edges[detailed.edges.1])
+                .map_or(detailed.point, |(start, _)| start);
+            let first = &mut firsts[detailed.edges.0];
+            match first {
+                Some(seen) if edge.steps_to(seen) <= edge.steps_to(&point) => (),
+                _ => *first = Some(point),
+            }
+        }
+        firsts
+    }
+
+    /// The crossings as CSV, one row per crossing after a header, ignoring the origin.
+    pub fn intersections_csv(&self, other: &Self) -> String {
+        let origin = Point { x: 0, y: 0 };
+        let mut csv = String::from("x,y,steps_self,steps_other,steps_total,manhattan\n");
+        for detailed in self.detailed_intersections(other) {
+            if detailed.point == origin {
+                continue;
+            }
+            csv.push_str(&format!("{},{},{},{},{},{}\n",
+                detailed.point.x, detailed.point.y,
+                detailed.steps_self, detailed.steps_other, detailed.steps_self + detailed.steps_other,
+                detailed.point.distance_from_origin()));
+        }
+        csv
+    }
+}
+
+/// Parses every wire in `input`.
+///
+/// Wires may be one per line, separated by `;`, or separated by blank lines.
+/// Each wire must still be made of comma separated edges like `R8`, or this panics.
+pub fn read_wires(input: &str) -> Vec<Wire> {
+    parse_wires(input).unwrap_or_else(|(_, err)| panic!("{}", err))
+}
+
+/// Like `read_wires`, but a malformed wire fails with the index of its line,
+/// counting from 0.
+pub fn parse_wires(input: &str) -> Result<Vec<Wire>, (usize, WireParseError)> {
+    input.lines()
+        .enumerate()
+        .flat_map(|(index, line)| line.split(';').map(move |wire_str| (index, wire_str.trim())))
+        .filter(|(_, wire_str)| !wire_str.is_empty())
+        .map(|(index, wire_str)| Wire::try_from_string(wire_str).map_err(|err| (index, err)))
+        .collect()
+}
+
+/// A wire of `edges` edges from the origin, each in a random direction with a
+/// random magnitude from 1 to `max_magnitude`.
+///
+/// The same `rng_seed` always gives the same wire, so failures found with it reproduce.
+pub fn random_wire(rng_seed: u64, edges: usize, max_magnitude: i64) -> Wire {
+    assert!(max_magnitude > 0);
+    let mut rng = SplitMix64(rng_seed);
+    let mut current_position = Point { x: 0, y: 0 };
+
+    let edges = (0..edges)
+        .map(|_| {
+            let direction = match rng.below(4) {
+                0 => Direction::Up,
+                1 => Direction::Down,
+                2 => Direction::Left,
+                _ => Direction::Right,
+            };
+            let edge = Edge {
+                direction,
+                magnitude: 1 + rng.below(max_magnitude as u64) as i64,
+                origin: current_position,
+            };
+            current_position = edge.get_endpoint();
+            edge
+        })
+        .collect();
+
+    Wire::from_edges(edges)
+}
+
+/// A small seeded generator, good enough for making test wires.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// Reads and parses every wire in the file at `path`, decompressing it first if it is gzipped.
+///
+/// Gzip data is recognized by its magic bytes, whatever the file is called, and
+/// anything else is read as plain text. See `read_wires` for the layout; a malformed
+/// wire fails as `InvalidData`, naming its line and edge.
+pub fn wires_from_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<Wire>> {
+    let mut bytes = fs::read(path)?;
+    if gzip::is_gzip(&bytes) {
+        bytes = gzip::decompress(&bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    }
+    let input = String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    parse_wires(&input).map_err(|(index, err)| io::Error::new(io::ErrorKind::InvalidData,
+        format!("Line {}: {}", index + 1, err)))
+}
+
+/// Finds crossings between every unordered pair of `wires`, using `get_intersections`
+/// on each pair in turn.
+///
+/// Results are ordered by wire pair, then like any other `Intersection`.
+pub fn pairwise_intersections(wires: &[Wire]) -> Vec<TaggedIntersection> {
+    let mut result = Vec::<TaggedIntersection>::new();
+    for (index, wire) in wires.iter().enumerate() {
+        for (other_index, other_wire) in wires.iter().enumerate().skip(index + 1) {
+            let mut intersections = wire.get_intersections(other_wire);
+            intersections.sort();
+            result.extend(intersections.into_iter().map(|intersection| TaggedIntersection {
+                wires: (index, other_index),
+                intersection,
+            }));
+        }
+    }
+    result
+}
+
+/// Finds crossings between every pair of different `wires` in a single sweep.
+///
+/// Vertical edges are indexed by x once, so each horizontal edge only visits the
+/// vertical edges within its span rather than every edge of every other wire.
+/// Edges running along each other are found among those on the same line, and meet
+/// once like in `detailed_intersections`. Diagonal edges don't line up with either
+/// axis, so they're checked against every edge of the other wires. The shared origin
+/// is not reported.
+pub fn sweep_intersections(wires: &[Wire]) -> Vec<TaggedIntersection> {
+    let mut horizontal_edges = Vec::<(usize, usize, Edge)>::new();
+    let mut vertical_edges = Vec::<(usize, usize, Edge)>::new();
+    let mut diagonal_edges = Vec::<(usize, usize, Edge)>::new();
+    for (wire_index, wire) in wires.iter().enumerate() {
+        for (edge_index, edge) in wire.iter().enumerate() {
+            match edge.direction {
+                Direction::Up | Direction::Down => vertical_edges.push((wire_index, edge_index, edge)),
+                Direction::Left | Direction::Right => horizontal_edges.push((wire_index, edge_index, edge)),
+                Direction::UpLeft | Direction::UpRight | Direction::DownLeft | Direction::DownRight => {
+                    diagonal_edges.push((wire_index, edge_index, edge))
+                },
+            }
+        }
+    }
+    vertical_edges.sort_by_key(|(_, _, edge)| edge.origin.x);
+    horizontal_edges.sort_by_key(|(_, _, edge)| edge.origin.y);
+
+    let origin = Point { x: 0, y: 0 };
+    let mut result = Vec::<TaggedIntersection>::new();
+    let mut push_crossing = |(a_wire, a_index, a_edge): &(usize, usize, Edge), (b_wire, b_index, b_edge): &(usize, usize, Edge)| {
+        if a_wire == b_wire {
+            return;
+        }
+
+        match a_edge.get_intersection(b_edge).or_else(|| a_edge.get_overlap(b_edge)) {
+            Some(point) if point != origin => {
+                let a_steps = wires[*a_wire].steps_to_edge(*a_index) + a_edge.steps_to(&point);
+                let b_steps = wires[*b_wire].steps_to_edge(*b_index) + b_edge.steps_to(&point);
+
+                // The wires are tagged lower index first, so the steps follow that order
+                let intersection = if a_wire < b_wire {
+                    Intersection::new(a_steps, b_steps, point)
+                } else {
+                    Intersection::new(b_steps, a_steps, point)
+                };
+                result.push(TaggedIntersection {
+                    wires: (*a_wire.min(b_wire), *a_wire.max(b_wire)),
+                    intersection,
+                });
+            },
+            _ => (),
+        }
+    };
+
+    for horizontal in horizontal_edges.iter() {
+        let (_, _, h_edge) = horizontal;
+        let endpoint = h_edge.get_endpoint();
+        let min_x = h_edge.origin.x.min(endpoint.x);
+        let max_x = h_edge.origin.x.max(endpoint.x);
+
+        let start = vertical_edges.partition_point(|(_, _, edge)| edge.origin.x < min_x);
+        let candidates = vertical_edges[start..].iter()
+            .take_while(|(_, _, edge)| edge.origin.x <= max_x);
+
+        for vertical in candidates {
+            push_crossing(horizontal, vertical);
+        }
+    }
+
+    // Both lists are sorted by the line their edges lie along, so only edges in the
+    // same run can overlap
+    let same_line = |a: &(usize, usize, Edge), b: &(usize, usize, Edge)| a.2.colinear(&b.2);
+    for line in horizontal_edges.chunk_by(same_line).chain(vertical_edges.chunk_by(same_line)) {
+        for (position, edge) in line.iter().enumerate() {
+            for other in &line[position + 1..] {
+                push_crossing(edge, other);
+            }
+        }
+    }
+
+    for (position, diagonal) in diagonal_edges.iter().enumerate() {
+        // Later diagonals only, so each pair of them is met once
+        let others = horizontal_edges.iter()
+            .chain(vertical_edges.iter())
+            .chain(diagonal_edges[position + 1..].iter());
+        for other in others {
+            push_crossing(diagonal, other);
+        }
+    }
+
+    result.sort_by(|a, b| a.wires.cmp(&b.wires).then(a.intersection.cmp(&b.intersection)));
+    result
+}
+
+/// Draws `wires` like the puzzle's examples, with y increasing upward and a
+/// border of `.` around them.
+///
+/// Edges are `-`, `|`, `/` or `\`, turns are `+`, the origin is `o` and points
+/// that more than one wire visits are `X`. A grid with more than
+/// `RENDER_CELL_LIMIT` cells is described by its size instead.
+pub fn render(wires: &[Wire]) -> String {
+    let points = || wires.iter().flat_map(|wire| wire.points());
+    let min_x = points().map(|point| point.x).min().unwrap_or(0) - 1;
+    let max_x = points().map(|point| point.x).max().unwrap_or(0) + 1;
+    let min_y = points().map(|point| point.y).min().unwrap_or(0) - 1;
+    let max_y = points().map(|point| point.y).max().unwrap_or(0) + 1;
+
+    let width = (max_x - min_x) as u64 + 1;
+    let height = (max_y - min_y) as u64 + 1;
+    if width * height > RENDER_CELL_LIMIT {
+        return format!("{} by {} grid, too large to render\n", width, height);
+    }
+
+    let width = width as usize;
+    let mut cells = vec!['.'; width * height as usize];
+    // The first wire to visit each cell, to tell where a different wire crosses it
+    let mut owners: Vec<Option<usize>> = vec![None; cells.len()];
+    let index = |point: &Point| (max_y - point.y) as usize * width + (point.x - min_x) as usize;
+
+    for (wire_index, wire) in wires.iter().enumerate() {
+        for (edge_index, edge) in wire.iter().enumerate() {
+            let line = match edge.direction {
+                Direction::Up | Direction::Down => '|',
+                Direction::Left | Direction::Right => '-',
+                Direction::UpRight | Direction::DownLeft => '/',
+                Direction::UpLeft | Direction::DownRight => '\\',
+            };
+            let turns = edge_index + 1 < wire.edges.len();
+            let endpoint = edge.get_endpoint();
+
+            for point in edge.points() {
+                let cell = index(&point);
+                cells[cell] = match owners[cell] {
+                    Some(owner) if owner != wire_index => 'X',
+                    // A wire crossing itself is drawn like a turn
+                    Some(_) => '+',
+                    None if turns && point == endpoint => '+',
+                    None => line,
+                };
+                owners[cell].get_or_insert(wire_index);
+            }
+        }
+    }
+    cells[index(&Point { x: 0, y: 0 })] = 'o';
+
+    let mut grid = String::with_capacity(cells.len() + height as usize);
+    for row in cells.chunks(width) {
+        grid.extend(row);
+        grid.push('\n');
+    }
+    grid
+}
+
+/// Every point, other than the shared origin, that at least three of `wires` pass through.
+///
+/// Each wire is rasterized into a grid counting how many wires cover each point, so a
+/// wire crossing itself still only counts once. Points come back nearest the origin first.
+pub fn triple_points(wires: &[Wire]) -> Vec<Point> {
+    let mut result: Vec<Point> = occupancy(wires)
+        .into_iter()
+        .filter(|(_, coverage)| *coverage >= 3)
+        .map(|((x, y), _)| Point { x, y })
+        .filter(|point| *point != Point { x: 0, y: 0 })
+        .collect();
+
+    result.sort_by_key(|point| (point.distance_from_origin(), point.x, point.y));
+    result
+}
+
+/// How many of `wires` visit each point, keyed by `(x, y)`.
+fn occupancy(wires: &[Wire]) -> HashMap<(i64, i64), usize> {
+    let mut grid = HashMap::<(i64, i64), usize>::new();
+    for wire in wires {
+        let visited: HashSet<(i64, i64)> = wire.points().map(|point| (point.x, point.y)).collect();
+        for cell in visited {
+            *grid.entry(cell).or_insert(0) += 1;
+        }
+    }
+    grid
+}
+
+/// Wires are equal when they trace the same path, regardless of how it is split into edges.
+impl PartialEq for Wire {
+    fn eq(&self, other: &Self) -> bool {
+        self.points().eq(other.points())
+    }
+}
+
+pub struct WireIter<'a> {
+    data: &'a Wire,
+    index: usize,
+}
+
+impl<'a> Iterator for WireIter<'a> {
+    type Item = Edge;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.data.edges.len() {
+            None
+        } else {
+            self.index += 1;
+            Some(self.data.edges[self.index - 1])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_equality_and_order() {
+        assert_eq!(Point { x: 1, y: 2 }, Point { x: 1, y: 2 });
+        assert_ne!(Point { x: 1, y: 2 }, Point { x: 2, y: 1 });
+        assert_ne!(Point { x: 1, y: 2 }, Point { x: 1, y: 3 });
+
+        // Closer points come first, and ties at the same distance are still ordered
+        assert!(Point { x: 0, y: 2 } < Point { x: -3, y: 0 });
+        assert!(Point { x: -1, y: 1 } < Point { x: 1, y: -1 });
+        assert!(Point { x: 1, y: -1 } < Point { x: 1, y: 1 });
+        assert_eq!(Point { x: 2, y: 1 }.cmp(&Point { x: 1, y: 2 }), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_intersection_equality_and_order() {
+        let point = Point { x: 3, y: -4 };
+        assert_eq!(Intersection::new(5, 7, point), Intersection::new(5, 7, point));
+        assert_eq!(Intersection::new(5, 7, point), Intersection::new(7, 5, point));
+        assert_ne!(Intersection::new(5, 7, point), Intersection::new(6, 7, point));
+        assert_ne!(Intersection::new(5, 7, point), Intersection::new(5, 7, Point { x: -4, y: 3 }));
+
+        assert!(Intersection::new(5, 7, point) < Intersection::new(6, 7, Point { x: 0, y: 1 }));
+        assert!(Intersection::new(5, 7, Point { x: -4, y: 3 }) < Intersection::new(5, 7, point));
+    }
+
+    #[test]
+    fn test_crossing_midsection() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 10,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let crossing_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 10,
+            origin: Point { x: 5, y: -5 },
+        };
+
+        assert!(crossing_edge.is_crossing(&base_edge));
+        assert!(base_edge.is_crossing(&crossing_edge));
+    }
+
+    #[test]
+    fn test_crossing_at_endpoints() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 10,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let crossing_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 10,
+            origin: Point { x: 0, y: -5 },
+        };
+
+        assert!(crossing_edge.is_crossing(&base_edge));
+        assert!(base_edge.is_crossing(&crossing_edge));
+    }
+
+    #[test]
+    fn test_parallel_not_crossing() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 2,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let crossing_edge = Edge {
+            direction: Direction::Left,
+            magnitude: 5,
+            origin: Point { x: 2, y: 0 },
+        };
+
+        assert!(!crossing_edge.is_crossing(&base_edge));
+        assert!(!base_edge.is_crossing(&crossing_edge));
+    }
+
+    #[test]
+    fn test_crossing_midsection_is_not_a_corner() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 10,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let crossing_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 10,
+            origin: Point { x: 5, y: -5 },
+        };
+
+        let crossing = base_edge.get_crossing(&crossing_edge).unwrap();
+        assert_eq!(crossing.point, Point { x: 5, y: 0 });
+        assert!(!crossing.on_self_endpoint);
+        assert!(!crossing.on_other_endpoint);
+    }
+
+    #[test]
+    fn test_crossing_at_one_endpoint() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 10,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let crossing_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 10,
+            origin: Point { x: 0, y: -5 },
+        };
+
+        let crossing = crossing_edge.get_crossing(&base_edge).unwrap();
+        assert_eq!(crossing.point, Point { x: 0, y: 0 });
+        assert!(!crossing.on_self_endpoint);
+        assert!(crossing.on_other_endpoint);
+    }
+
+    #[test]
+    fn test_crossing_corner_to_corner() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 10,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let crossing_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 5,
+            origin: Point { x: 10, y: -5 },
+        };
+
+        let crossing = base_edge.get_crossing(&crossing_edge).unwrap();
+        assert_eq!(crossing.point, Point { x: 10, y: 0 });
+        assert!(crossing.on_self_endpoint);
+        assert!(crossing.on_other_endpoint);
+    }
+
+    #[test]
+    fn test_interval_boundaries() {
+        let interval = Interval::new(2, 5);
+
+        assert!(interval.contains(2));
+        assert!(interval.contains(4));
+        assert!(!interval.contains(5));
+        assert!(!interval.contains(1));
+    }
+
+    #[test]
+    fn test_interval_overlaps_at_boundaries() {
+        let interval = Interval::new(0, 3);
+
+        assert!(!interval.overlaps(&Interval::new(3, 5)));
+        assert!(!Interval::new(3, 5).overlaps(&interval));
+        assert!(interval.overlaps(&Interval::new(2, 5)));
+        assert!(interval.overlaps(&Interval::new(-1, 1)));
+        assert!(interval.overlaps(&Interval::new(1, 2)));
+    }
+
+    #[test]
+    fn test_shared_endpoints_count() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 2,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let continuing_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 3,
+            origin: Point { x: 2, y: 0 },
+        };
+
+        let turning_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 3,
+            origin: Point { x: 2, y: 0 },
+        };
+
+        assert!(base_edge.is_overlapping(&continuing_edge));
+        assert!(base_edge.is_crossing(&turning_edge));
+    }
+
+    #[test]
+    fn test_interval_ignores_direction() {
+        let up_edge = Edge {
+            direction: Direction::Up,
+            magnitude: 4,
+            origin: Point { x: 1, y: -1 },
+        };
+
+        let down_edge = Edge {
+            direction: Direction::Down,
+            magnitude: 4,
+            origin: Point { x: 1, y: 3 },
+        };
+
+        assert_eq!(up_edge.as_interval(), Interval::new(-1, 4));
+        assert_eq!(up_edge.as_interval(), down_edge.as_interval());
+
+        let right_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 4,
+            origin: Point { x: -1, y: 1 },
+        };
+
+        let left_edge = Edge {
+            direction: Direction::Left,
+            magnitude: 4,
+            origin: Point { x: 3, y: 1 },
+        };
+
+        assert_eq!(right_edge.as_interval(), left_edge.as_interval());
+
+        // A Down edge ending just above a crossing edge doesn't touch it
+        let short_edge = Edge {
+            direction: Direction::Down,
+            magnitude: 2,
+            origin: Point { x: 1, y: 3 },
+        };
+        let crossing_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 4,
+            origin: Point { x: -1, y: 0 },
+        };
+        assert!(!short_edge.is_crossing(&crossing_edge));
+        assert!(down_edge.is_crossing(&crossing_edge));
+    }
+
+    #[test]
+    fn test_diagonal_crossing() {
+        let diagonal_edge = Edge {
+            direction: Direction::UpRight,
+            magnitude: 6,
+            origin: Point { x: -2, y: -1 },
+        };
+
+        let horizontal_edge = Edge {
+            direction: Direction::Left,
+            magnitude: 5,
+            origin: Point { x: 4, y: 2 },
+        };
+
+        assert!(diagonal_edge.is_crossing(&horizontal_edge));
+        assert!(horizontal_edge.is_crossing(&diagonal_edge));
+        assert_eq!(diagonal_edge.get_intersection(&horizontal_edge), Some(Point { x: 1, y: 2 }));
+        assert_eq!(horizontal_edge.get_intersection(&diagonal_edge), Some(Point { x: 1, y: 2 }));
+
+        // These pass each other halfway between grid points
+        let other_diagonal = Edge {
+            direction: Direction::DownRight,
+            magnitude: 4,
+            origin: Point { x: -1, y: 3 },
+        };
+        assert!(!diagonal_edge.is_crossing(&other_diagonal));
+
+        let wire_0 = Wire::from_string("UR4,L6");
+        let wire_1 = Wire::from_string("L2,U4");
+        assert_eq!(wire_0.get_intersections(&wire_1), vec![Intersection::new(10, 6, Point { x: -2, y: 4 })]);
+        assert_eq!(sweep_intersections(&[wire_0, wire_1])[0].intersection, Intersection::new(10, 6, Point { x: -2, y: 4 }));
+    }
+
+    #[test]
+    fn test_parallel_lines_do_not_overlap() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 2,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let other_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 2,
+            origin: Point { x: 0, y: 5 },
+        };
+
+        assert!(!base_edge.is_overlapping(&other_edge));
+    }
+
+    #[test]
+    fn test_overlapping_edges() {
+        let base_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 5,
+            origin: Point { x: 0, y: 0 },
+        };
+
+        let other_edge = Edge {
+            direction: Direction::Right,
+            magnitude: 5,
+            origin: Point { x: 3, y: 0 },
+        };
+
+        assert_eq!(base_edge.get_overlap(&other_edge), Some(Point { x: 3, y: 0 }));
+        assert_eq!(base_edge.get_intersection(&other_edge), None);
+
+        // Running towards each other costs the same anywhere along the shared stretch
+        let wire_0 = Wire::from_string("U1,R6");
+        let wire_1 = Wire::from_string("R8,U1,L4");
+        assert_eq!(wire_0.get_intersections(&wire_1), vec![Intersection::new(5, 13, Point { x: 4, y: 1 })]);
+        assert_eq!(sweep_intersections(&[wire_0, wire_1])[0].intersection, Intersection::new(5, 13, Point { x: 4, y: 1 }));
+
+        // First edges that run along each other out of the origin meet one step in
+        let wire_0 = Wire::from_string("R8,U2");
+        let wire_1 = Wire::from_string("R5,D3");
+        let closest = || Intersection::new(1, 1, Point { x: 1, y: 0 });
+        assert_eq!(wire_0.closest_by_manhattan(&wire_1), Some(closest()));
+        assert_eq!(wire_0.fewest_steps(&wire_1), Some(closest()));
+        assert_eq!(sweep_intersections(&[wire_0, wire_1])[0].intersection, closest());
+    }
+
+    #[test]
+    fn test_wire_path_equality() {
+        let split = Wire::from_string("R3,R4");
+        let joined = Wire::from_string("R7");
+
+        assert!(split == joined);
+        assert_ne!(split.edges.len(), joined.edges.len());
+        assert!(split != Wire::from_string("R3,U4"));
+    }
+
+    #[test]
+    fn test_sweep_intersections_tags_wire_pairs() {
+        let wires = vec![
+            Wire::from_string("U2,R10"),
+            Wire::from_string("R4,U6"),
+            Wire::from_string("R7,U6"),
+        ];
+
+        let found: Vec<((usize, usize), Point, u64)> = sweep_intersections(&wires)
+            .into_iter()
+            .map(|tagged| (tagged.wires, tagged.intersection.point, tagged.intersection.distance))
+            .collect();
+
+        assert_eq!(found, vec![
+            ((0, 1), Point { x: 4, y: 2 }, 12),
+            ((0, 2), Point { x: 7, y: 2 }, 18),
+            // The first edges of 1 and 2 run along each other out of the origin
+            ((1, 2), Point { x: 1, y: 0 }, 2),
+            ((1, 2), Point { x: 4, y: 0 }, 8),
+        ]);
+    }
+
+    #[test]
+    fn test_pairwise_intersections_of_three_wires() {
+        // Each pair crosses once, at the corners of a triangle
+        let wires = vec![
+            Wire::from_string("R2,U5"),
+            Wire::from_string("U3,R6"),
+            Wire::from_string("L1,U1,R8,U6"),
+        ];
+
+        let found: Vec<((usize, usize), Point, u64)> = pairwise_intersections(&wires)
+            .into_iter()
+            .map(|tagged| (tagged.wires, tagged.intersection.point, tagged.intersection.distance))
+            .collect();
+
+        assert_eq!(found, vec![
+            ((0, 1), Point { x: 2, y: 3 }, 10),
+            ((0, 2), Point { x: 2, y: 1 }, 8),
+            ((1, 2), Point { x: 0, y: 1 }, 4),
+        ]);
+        assert_eq!(pairwise_intersections(&wires[..1]).len(), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let wires = read_wires("R8,U5,L5,D3\nU7,R6,D4,L4\n");
+        let expected = [
+            "...........",
+            ".+-----+...",
+            ".|.....|...",
+            ".|..+--X-+.",
+            ".|..|..|.|.",
+            ".|.-X--+.|.",
+            ".|..|....|.",
+            ".|.......|.",
+            ".o-------+.",
+            "...........",
+        ];
+        assert_eq!(render(&wires), expected.join("\n") + "\n");
+
+        let wires = read_wires("R5000,U5000\nU5000,R5000\n");
+        assert_eq!(render(&wires), "5003 by 5003 grid, too large to render\n");
+    }
+
+    #[test]
+    fn test_both_parts() {
+        let wire_0 = Wire::from_string("R8,U5,L5,D3");
+        let wire_1 = Wire::from_string("U7,R6,D4,L4");
+        assert_eq!(wire_0.closest_by_manhattan(&wire_1).map(|closest| closest.point), Some(Point { x: 3, y: 3 }));
+        assert_eq!(wire_0.fewest_steps(&wire_1).map(|fewest| fewest.distance), Some(30));
+
+        // Both crossings happen to be the same distance along either wire
+        let closest = wire_0.closest_by_manhattan(&wire_1).unwrap();
+        assert_eq!((closest.steps_a(), closest.steps_b()), (20, 20));
+        assert_eq!(closest.steps_a() + closest.steps_b(), closest.distance);
+
+        let wire_0 = Wire::from_string("R75,D30,R83,U83,L12,D49,R71,U7,L72");
+        let wire_1 = Wire::from_string("U62,R66,U55,R34,D71,R55,D58,R83");
+        let closest = wire_0.closest_by_manhattan(&wire_1).unwrap();
+        let fewest = wire_0.fewest_steps(&wire_1).unwrap();
+        assert_eq!(closest.point.distance_from_origin(), 159);
+        assert_eq!(fewest.distance, 610);
+        assert_eq!(fewest.steps_a() + fewest.steps_b(), fewest.distance);
+        // The two answers come from different crossings
+        assert!(closest.distance > fewest.distance);
+        assert!(closest.point < fewest.point);
+
+        let wire_0 = Wire::from_string("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51");
+        let wire_1 = Wire::from_string("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7");
+        assert_eq!(wire_0.closest_by_manhattan(&wire_1).map(|closest| closest.point.distance_from_origin()), Some(135));
+        assert_eq!(wire_0.fewest_steps(&wire_1).map(|fewest| fewest.distance), Some(410));
+
+        assert!(Wire::from_string("U3").closest_by_manhattan(&Wire::from_string("D3")).is_none());
+    }
+
+    #[test]
+    fn test_closest_by_euclidean_sq() {
+        let far = Point { x: i64::MIN, y: i64::MAX };
+        assert_eq!(far.euclidean_sq_from_origin(), (1u128 << 126) + (i64::MAX as u128).pow(2));
+
+        let wire_0 = Wire::from_string("U3,R5,D10");
+        let wire_1 = Wire::from_string("R7,U5,L4,D4");
+
+        let closest = wire_0.closest_by_euclidean_sq(&wire_1).unwrap();
+        assert_eq!(closest.point, Point { x: 3, y: 3 });
+
+        // Manhattan distance prefers the crossing on the x axis instead
+        let by_manhattan = wire_0.get_intersections(&wire_1)
+            .into_iter()
+            .map(|intersection| intersection.point)
+            .min()
+            .unwrap();
+        assert_eq!(by_manhattan, Point { x: 5, y: 0 });
+    }
+
+    #[test]
+    fn test_get_intersections_skips_origin() {
+        // Both first edges run along each other out of the origin
+        let wire_0 = Wire::from_string("R8,U2");
+        let wire_1 = Wire::from_string("R5,D3");
+
+        let intersections = wire_0.get_intersections(&wire_1);
+        assert!(intersections.iter().all(|intersection| intersection.point != Point { x: 0, y: 0 }));
+        assert!(intersections.iter().any(|intersection| intersection.point == Point { x: 1, y: 0 }));
+    }
+
+    #[test]
+    fn test_validate_connectivity() {
+        assert_eq!(Wire::from_string("R8,U5,L5,D3").validate_connectivity(), Ok(()));
+
+        let edges = vec![
+            Edge {
+                direction: Direction::Right,
+                magnitude: 8,
+                origin: Point { x: 0, y: 0 },
+            },
+            Edge {
+                direction: Direction::Up,
+                magnitude: 5,
+                origin: Point { x: 8, y: 1 },
+            },
+        ];
+        let cumulative_lengths = Wire::accumulate_lengths(&edges);
+        let disconnected = Wire {
+            edges,
+            cumulative_lengths,
+        };
+
+        assert_eq!(disconnected.validate_connectivity(), Err(WireError::Disconnected {
+            index: 1,
+            expected: Point { x: 8, y: 0 },
+            found: Point { x: 8, y: 1 },
+        }));
+    }
+
+    #[test]
+    fn test_read_wires_layouts() {
+        let expected = vec![Wire::from_string("R8,U5,L5,D3"), Wire::from_string("U7,R6,D4,L4")];
+
+        assert!(read_wires("R8,U5,L5,D3\nU7,R6,D4,L4\n") == expected);
+        assert!(read_wires("R8,U5,L5,D3;U7,R6,D4,L4\n") == expected);
+        assert!(read_wires("R8,U5,L5,D3 ; U7,R6,D4,L4") == expected);
+        assert!(read_wires("R8,U5,L5,D3\n\nU7,R6,D4,L4\n\n") == expected);
+        assert!(read_wires("R8,U5,L5,D3\r\n\r\nU7,R6,D4,L4\r\n") == expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unrecognized direction!")]
+    fn test_read_wires_rejects_malformed() {
+        read_wires("R8,U5;X3,U7");
+    }
+
+    #[test]
+    fn test_try_from_string() {
+        assert!(Wire::try_from_string("R8,UL3,U5") == Ok(Wire::from_string("R8,UL3,U5")));
+
+        let failure = |string: &str| Wire::try_from_string(string).err().unwrap();
+        assert_eq!(failure("R8,X5"), WireParseError::UnknownDirection { token: "X5".to_string() });
+        assert_eq!(failure("U"), WireParseError::BadMagnitude { token: "U".to_string() });
+        assert_eq!(failure("Uabc"), WireParseError::BadMagnitude { token: "Uabc".to_string() });
+        assert_eq!(failure("R-3"), WireParseError::BadMagnitude { token: "R-3".to_string() });
+        assert_eq!(failure("R8,,U5"), WireParseError::EmptyToken { token: "".to_string() });
+        assert_eq!(failure("R8, ,U5"), WireParseError::EmptyToken { token: " ".to_string() });
+
+        assert!(Wire::try_from_string("R8, U5 ,L5,\tD3") == Ok(Wire::from_string("R8,U5,L5,D3")));
+        assert_eq!(failure("R8, X5"), WireParseError::UnknownDirection { token: "X5".to_string() });
+
+        let (index, err) = parse_wires("R8,U5\n\nU7;R6,D4,L4x\n").err().unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(err, WireParseError::BadMagnitude { token: "L4x".to_string() });
+    }
+
+    #[test]
+    fn test_intersection_hull() {
+        let zigzag = Wire::from_string("R2,U6,R2,D6,R2,U6");
+        let loop_wire = Wire::from_string("U1,R7,U4,L7");
+
+        // (4, 1) and (4, 5) lie along the hull's edges
+        assert_eq!(zigzag.intersection_hull(&loop_wire), vec![
+            Point { x: 2, y: 1 },
+            Point { x: 6, y: 1 },
+            Point { x: 6, y: 5 },
+            Point { x: 2, y: 5 },
+        ]);
+    }
+
+    #[test]
+    fn test_intersection_hull_degenerate() {
+        let wire_0 = Wire::from_string("R8,U5,L5,D3");
+        let wire_1 = Wire::from_string("U7,R6,D4,L4");
+
+        assert_eq!(wire_0.intersection_hull(&wire_1), vec![Point { x: 3, y: 3 }, Point { x: 6, y: 5 }]);
+        assert!(wire_0.intersection_hull(&Wire::from_string("L3")).is_empty());
+    }
+
+    #[test]
+    fn test_intersections_csv() {
+        let wire_0 = Wire::from_string("R8,U5,L5,D3");
+        let wire_1 = Wire::from_string("U7,R6,D4,L4");
+
+        let csv = wire_0.intersections_csv(&wire_1);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "x,y,steps_self,steps_other,steps_total,manhattan");
+        assert!(lines.contains(&"6,5,15,15,30,11"));
+        assert!(lines.contains(&"3,3,20,20,40,6"));
+    }
+
+    #[test]
+    fn test_minimum_gap() {
+        let wire_0 = Wire::from_string("U4,R6");
+        let wire_1 = Wire::from_string("R4,U2,R3");
+        // From (4, 4) down to (4, 2)
+        assert_eq!(wire_0.minimum_gap(&wire_1), 2);
+        assert_eq!(wire_1.minimum_gap(&wire_0), 2);
+
+        let crossing = Wire::from_string("R2,U6");
+        assert_eq!(wire_0.minimum_gap(&crossing), 0);
+    }
+
+    #[test]
+    fn test_detailed_intersection_edges() {
+        let wire_0 = Wire::from_string("R8,U5,L5,D3");
+        let wire_1 = Wire::from_string("U7,R6,D4,L4");
+
+        let detailed = wire_0.detailed_intersections(&wire_1);
+        let at = |point: Point| detailed.iter().find(|detailed| detailed.point == point).unwrap().edges;
+        // L5 crosses D4, then D3 crosses L4
+        assert_eq!(at(Point { x: 6, y: 5 }), (2, 2));
+        assert_eq!(at(Point { x: 3, y: 3 }), (3, 3));
+
+        assert_eq!(wire_1.detailed_intersections(&wire_0).len(), detailed.len());
+    }
+
+    #[test]
+    fn test_enclosed_area() {
+        assert_eq!(Wire::from_string("R1,U1,L1,D1").enclosed_area(), Some(1));
+        assert_eq!(Wire::from_string("U1,R1,D1,L1").enclosed_area(), Some(1));
+        // An L shape: a 3x3 square missing its top right 2x2
+        assert_eq!(Wire::from_string("R3,U1,L2,U2,L1,D3").enclosed_area(), Some(5));
+        assert_eq!(Wire::from_string("R1,U1,L1").enclosed_area(), None);
+    }
+
+    #[test]
+    fn test_triple_points() {
+        let wires = read_wires("R4,U4\nU2,R4\nR2,U2,R4\nU8");
+        // All three of the first wires meet at (4, 2), and only two of them anywhere else
+        assert_eq!(triple_points(&wires), vec![Point { x: 4, y: 2 }]);
+
+        assert_eq!(triple_points(&wires[..2]), vec![]);
+    }
+
+    #[test]
+    fn test_first_crossings_per_edge() {
+        let wire_0 = Wire::from_string("R10,U1,L10");
+        let wire_1 = Wire::from_string("U2,R5,D4,R3,U4");
+
+        // Both horizontal edges cross at x = 5 and x = 8, but walk it in opposite directions
+        assert_eq!(wire_0.first_crossings_per_edge(&wire_1),
+            vec![Some(Point { x: 5, y: 0 }), None, Some(Point { x: 8, y: 1 })]);
+
+        // The last edge runs into the overlap from its far end
+        let wire_1 = Wire::from_string("U1,R7");
+        assert_eq!(wire_0.first_crossings_per_edge(&wire_1), vec![None, None, Some(Point { x: 7, y: 1 })]);
+    }
+
+    #[test]
+    fn test_wires_from_gzip_file() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let plain = wires_from_file(dir.join("input")).unwrap();
+        let gzipped = wires_from_file(dir.join("input.gz")).unwrap();
+
+        assert_eq!(plain.len(), 2);
+        assert!(plain == gzipped);
+        assert!(matches!(wires_from_file(dir.join("missing")), Err(err) if err.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_random_wire() {
+        assert!(random_wire(7, 20, 5) == random_wire(7, 20, 5));
+        assert!(random_wire(7, 20, 5) != random_wire(8, 20, 5));
+
+        let wire = random_wire(7, 20, 5);
+        assert_eq!(wire.iter().count(), 20);
+        assert!(wire.iter().all(|edge| (1..=5).contains(&edge.magnitude)));
+        assert_eq!(wire.validate_connectivity(), Ok(()));
+    }
+
+    #[test]
+    fn test_intersections_match_grid() {
+        let mut checked = 0;
+        for seed in 0..500 {
+            let wires = [random_wire(2 * seed, 8, 6), random_wire(2 * seed + 1, 8, 6)];
+            let mut intersections: Vec<(i64, i64, u64)> = wires[0].get_intersections(&wires[1])
+                .into_iter()
+                .map(|intersection| (intersection.point.x, intersection.point.y, intersection.distance))
+                .collect();
+            intersections.sort();
+            let mut swept: Vec<(i64, i64, u64)> = sweep_intersections(&wires)
+                .into_iter()
+                .map(|tagged| (tagged.intersection.point.x, tagged.intersection.point.y, tagged.intersection.distance))
+                .collect();
+            swept.sort();
+            assert_eq!(intersections, swept, "seeds {} and {}", 2 * seed, 2 * seed + 1);
+
+            let mut crossings: Vec<(i64, i64)> = intersections.iter().map(|(x, y, _)| (*x, *y)).collect();
+            crossings.dedup();
+            let mut shared: Vec<(i64, i64)> = occupancy(&wires)
+                .into_iter()
+                .filter(|(cell, coverage)| *coverage == 2 && *cell != (0, 0))
+                .map(|(cell, _)| cell)
+                .collect();
+            shared.sort();
+
+            // Wires running along each other share every point there, but meet only once
+            if wires[0].iter().any(|edge| wires[1].iter().any(|other_edge| edge.is_overlapping(&other_edge))) {
+                assert!(crossings.iter().all(|crossing| shared.contains(crossing)), "seeds {} and {}", 2 * seed, 2 * seed + 1);
+            } else {
+                checked += 1;
+                assert_eq!(crossings, shared, "seeds {} and {}", 2 * seed, 2 * seed + 1);
+            }
+        }
+        assert!(checked > 100);
+    }
+
+    #[test]
+    fn test_cumulative_lengths() {
+        let wire = Wire::from_string("R8,U5,L5,D3");
+
+        assert_eq!(wire.cumulative_lengths, vec![0, 8, 13, 18]);
+        assert_eq!(wire.steps_to_edge(2), 13);
+    }
+}
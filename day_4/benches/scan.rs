@@ -0,0 +1,31 @@
+use std::time::Instant;
+
+use aoc_4::{count_valid_naive, count_valid_parallel, count_valid_skip_ahead, Order, Rules};
+
+
+// Input Range: 197487-673251
+const LOWER: u64 = 197487;
+const UPPER: u64 = 673251;
+const WORKERS: u64 = 4;
+const RULES: Rules = Rules::new(Order::NonDecreasing);
+
+fn main() {
+    let naive = run("naive", || count_valid_naive(LOWER, UPPER, RULES));
+    let skip_ahead = run("skip-ahead", || count_valid_skip_ahead(LOWER, UPPER, RULES));
+    let parallel = run("parallel", || count_valid_parallel(LOWER, UPPER, WORKERS, RULES));
+
+    assert_eq!(naive, skip_ahead);
+    assert_eq!(naive, parallel);
+}
+
+/// Times a full scan of the range and reports its throughput.
+fn run<F: Fn() -> usize>(name: &str, scan: F) -> usize {
+    let start = Instant::now();
+    let count = scan();
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let candidates = (UPPER - LOWER + 1) as f64;
+    println!("{:<10} found {} passwords in {:.4}s ({:.0} candidates/s)",
+        name, count, elapsed, candidates / elapsed);
+    count
+}
@@ -0,0 +1,79 @@
+/// A fixed-length set of bits, packed 64 to a word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    /// Creates a `BitVec` of `len` bits, all unset.
+    pub fn new(len: usize) -> Self {
+        BitVec {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len);
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn set(&mut self, index: usize) {
+        assert!(index < self.len);
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    /// Number of bits that are set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The bits set in both `self` and `other`, which must be the same length.
+    pub fn intersection(&self, other: &Self) -> Self {
+        assert_eq!(self.len, other.len);
+        BitVec {
+            words: self.words.iter().zip(other.words.iter()).map(|(a, b)| a & b).collect(),
+            len: self.len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_get() {
+        let mut bits = BitVec::new(130);
+        bits.set(0);
+        bits.set(64);
+        bits.set(129);
+
+        assert!(bits.get(0) && bits.get(64) && bits.get(129));
+        assert!(!bits.get(1) && !bits.get(63) && !bits.get(128));
+        assert_eq!(bits.count_ones(), 3);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let mut a = BitVec::new(10);
+        let mut b = BitVec::new(10);
+        a.set(2);
+        a.set(5);
+        b.set(5);
+        b.set(7);
+
+        let both = a.intersection(&b);
+        assert!(both.get(5));
+        assert_eq!(both.count_ones(), 1);
+    }
+}
@@ -0,0 +1,984 @@
+use std::ops::Index;
+use std::iter;
+use std::thread;
+
+mod bitvec;
+
+pub use bitvec::BitVec;
+
+
+/// Counts valid passwords in `[lower, upper]` by checking every number.
+pub fn count_valid_naive(lower: u64, upper: u64, rules: Rules) -> usize {
+    let mut silly_number = SillyNumber::new(lower);
+    let mut count = 0;
+    while silly_number.number <= upper {
+        if silly_number.is_valid_under(rules) {
+            count += 1;
+        }
+        silly_number.increment();
+    }
+    count
+}
+
+/// Counts valid passwords in `[lower, upper]`, skipping runs of numbers with out of order digits.
+pub fn count_valid_skip_ahead(lower: u64, upper: u64, rules: Rules) -> usize {
+    let mut silly_number = first_candidate(lower, rules);
+    let mut count = 0;
+    while silly_number.number <= upper {
+        if silly_number.is_valid_under(rules) {
+            count += 1;
+        }
+        silly_number.increment_skipping(rules);
+    }
+    count
+}
+
+/// Counts the puzzle's passwords in `[lower, upper]` for both parts in one scan.
+///
+/// Part 1 only needs two adjacent digits to match, while part 2 needs a pair that
+/// isn't part of a longer run. Both need the digits in order, so they share the skip-ahead.
+pub fn solve(lower: u64, upper: u64) -> (usize, usize) {
+    let rules = Rules::default();
+    let mut silly_number = first_candidate(lower, rules);
+    let mut counts = (0, 0);
+    while silly_number.number <= upper {
+        let (part1, part2) = silly_number.puzzle_parts();
+        counts.0 += part1 as usize;
+        counts.1 += part2 as usize;
+        silly_number.increment_skipping(rules);
+    }
+    counts
+}
+
+/// The fraction of numbers in `[lower, upper]` that are valid passwords, or 0 for an empty range.
+pub fn valid_density(lower: u64, upper: u64, rules: Rules) -> f64 {
+    if lower > upper {
+        return 0.0;
+    }
+    count_valid_skip_ahead(lower, upper, rules) as f64 / (upper - lower + 1) as f64
+}
+
+/// Marks the valid passwords in `[lower, upper]`, where bit `i` stands for `lower + i`.
+pub fn valid_bitset(lower: u64, upper: u64, rules: Rules) -> BitVec {
+    let len = if lower > upper { 0 } else { (upper - lower + 1) as usize };
+    let mut bits = BitVec::new(len);
+
+    let mut silly_number = first_candidate(lower, rules);
+    while silly_number.number <= upper {
+        if silly_number.is_valid_under(rules) {
+            bits.set((silly_number.number - lower) as usize);
+        }
+        silly_number.increment_skipping(rules);
+    }
+    bits
+}
+
+/// The first number at or above `lower` that the skip-ahead cannot rule out under `rules`.
+pub fn first_candidate(lower: u64, rules: Rules) -> SillyNumber {
+    let mut silly_number = SillyNumber::new(lower);
+    silly_number.skip_to_candidate(rules);
+    silly_number
+}
+
+/// The largest valid password strictly below `upper`, walking down past runs of ruled out numbers.
+pub fn last_valid_before(upper: u64, rules: Rules) -> Option<u64> {
+    valid_passwords_desc(0, upper.checked_sub(1)?, rules).next()
+}
+
+/// Every valid password in `[lower, upper]`, from the largest down, found lazily.
+pub fn valid_passwords_desc(lower: u64, upper: u64, rules: Rules) -> impl Iterator<Item = u64> {
+    let mut next = Some(SillyNumber::new(upper));
+    iter::from_fn(move || loop {
+        let silly_number = next.as_mut()?;
+        if !silly_number.skip_back_to_candidate(rules) || silly_number.number < lower {
+            next = None;
+            return None;
+        }
+
+        let number = silly_number.number;
+        let is_valid = silly_number.is_valid_under(rules);
+        if number == 0 {
+            next = None;
+        } else {
+            silly_number.decrement();
+        }
+        if is_valid {
+            return Some(number);
+        }
+    })
+}
+
+/// Splits `[lower, upper]` into one chunk per worker and counts each with the skip-ahead scan.
+pub fn count_valid_parallel(lower: u64, upper: u64, workers: u64, rules: Rules) -> usize {
+    scan_chunks(lower, upper, workers, |chunk_lower, chunk_upper| {
+        count_valid_skip_ahead(chunk_lower, chunk_upper, rules)
+    }).into_iter().sum()
+}
+
+/// Every valid password in `[lower, upper]`, in ascending order.
+pub fn valid_passwords(lower: u64, upper: u64, rules: Rules) -> Vec<u64> {
+    let mut passwords = Vec::new();
+    let mut silly_number = first_candidate(lower, rules);
+    while silly_number.number <= upper {
+        if silly_number.is_valid_under(rules) {
+            passwords.push(silly_number.number);
+        }
+        silly_number.increment_skipping(rules);
+    }
+    passwords
+}
+
+/// Like `valid_passwords`, with each worker collecting one chunk of the range.
+pub fn valid_passwords_parallel(lower: u64, upper: u64, workers: u64, rules: Rules) -> Vec<u64> {
+    // The chunks come back in order and don't overlap, so joining them keeps the list sorted
+    scan_chunks(lower, upper, workers, |chunk_lower, chunk_upper| {
+        valid_passwords(chunk_lower, chunk_upper, rules)
+    }).concat()
+}
+
+/// Splits `[lower, upper]` into one contiguous chunk per worker, returning each
+/// worker's result of `scan` in the order of their chunks.
+fn scan_chunks<T, F>(lower: u64, upper: u64, workers: u64, scan: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(u64, u64) -> T + Sync,
+{
+    assert!(workers > 0);
+    if lower > upper {
+        return Vec::new();
+    }
+
+    let chunk_size = (upper - lower) / workers + 1;
+    let scan = &scan;
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|worker| lower + worker * chunk_size)
+            .filter(|chunk_lower| *chunk_lower <= upper)
+            .map(|chunk_lower| {
+                let chunk_upper = (chunk_lower + chunk_size - 1).min(upper);
+                scope.spawn(move || scan(chunk_lower, chunk_upper))
+            })
+            .collect();
+
+        handles.into_iter()
+            .map(|handle| handle.join().expect("Worker panicked"))
+            .collect()
+    })
+}
+
+/// Walks every number in `[lower, upper]`, reporting what the skip-ahead scan made of each.
+pub fn diagnose(lower: u64, upper: u64, rules: Rules) -> Diagnostics {
+    Diagnostics {
+        next: lower,
+        upper,
+        rules,
+        candidate: first_candidate(lower, rules),
+    }
+}
+
+/// Why a number isn't a valid password.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// It contains a digit the rules forbid.
+    ForbiddenDigit(u8),
+    /// Its digits don't run in the required order.
+    OutOfOrder,
+    /// It has no run of identical digits of exactly the required length.
+    NoExactRun,
+    /// It has fewer different digits than required.
+    TooFewDistinctDigits,
+}
+
+/// What the skip-ahead scan did with a number.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Valid,
+    /// Jumped over without being checked.
+    Skipped(Rejection),
+    /// Checked and rejected.
+    Invalid(Rejection),
+}
+
+/// Iterator returned by [`diagnose`].
+///
+/// A number the scan jumped over comes out as `Valid` if it is actually a
+/// valid password, so a `Valid` between two candidates means a missed password.
+pub struct Diagnostics {
+    next: u64,
+    upper: u64,
+    rules: Rules,
+    candidate: SillyNumber,
+}
+
+impl Iterator for Diagnostics {
+    type Item = (u64, Outcome);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next > self.upper {
+            return None;
+        }
+        let number = self.next;
+        self.next += 1;
+
+        let outcome = if number < self.candidate.number {
+            match SillyNumber::new(number).rejection(self.rules) {
+                Some(rejection) => Outcome::Skipped(rejection),
+                None => Outcome::Valid,
+            }
+        } else {
+            let outcome = match self.candidate.rejection(self.rules) {
+                Some(rejection) => Outcome::Invalid(rejection),
+                None => Outcome::Valid,
+            };
+            self.candidate.increment_skipping(self.rules);
+            outcome
+        };
+        Some((number, outcome))
+    }
+}
+
+/// Which way the digits of a password must run, reading left to right.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Digits never decrease, as the puzzle requires.
+    NonDecreasing,
+    /// Digits never increase.
+    Mirror,
+    /// Digits follow the table given to `Rules::from_transition_table`.
+    ///
+    /// Nothing is known about which numbers that rules out, so none are skipped.
+    Custom,
+}
+
+impl Order {
+    /// Whether `digit` may not come just before `last_digit`.
+    ///
+    /// Digits are visited least significant first, so `last_digit` is to the right of `digit`.
+    /// Only `Rules` know a custom order's table, so nothing is out of a custom order here.
+    fn out_of_order(&self, last_digit: u8, digit: u8) -> bool {
+        match self {
+            Order::NonDecreasing => digit > last_digit,
+            Order::Mirror => digit < last_digit,
+            Order::Custom => false,
+        }
+    }
+
+    /// The transition table allowing exactly the digit pairs this order does.
+    const fn transition_table(&self) -> [u16; 10] {
+        let mut table = [0; 10];
+        let mut digit = 0;
+        while digit < 10 {
+            table[digit] = match self {
+                Order::NonDecreasing => ALL_DIGITS & !((1 << digit) - 1),
+                Order::Mirror => (1 << (digit + 1)) - 1,
+                Order::Custom => ALL_DIGITS,
+            };
+            digit += 1;
+        }
+        table
+    }
+}
+
+/// A transition table entry allowing any digit to come next.
+const ALL_DIGITS: u16 = (1 << 10) - 1;
+
+/// The constraints a password must satisfy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// Kept in step with `transitions`, which is built from it, so it can't be changed on its own.
+    order: Order,
+    /// Bit `n` of entry `d` is set when digit `n` may come just after digit `d`.
+    transitions: [u16; 10],
+    /// Bit `d` is set when digit `d` may not appear anywhere.
+    forbidden_digits: u16,
+    /// A password needs a run of exactly this many identical digits, not part of a longer run.
+    run_length: usize,
+    /// A password needs at least this many different digits.
+    min_distinct_digits: u32,
+}
+
+impl Rules {
+    pub const fn new(order: Order) -> Self {
+        Rules {
+            order,
+            transitions: order.transition_table(),
+            forbidden_digits: 0,
+            run_length: 2,
+            min_distinct_digits: 0,
+        }
+    }
+
+    /// Rules where each digit may only be followed by the digits set in its entry of `table`,
+    /// as bit `n` of `table[d]` for digit `n` after digit `d`, rather than by an `Order`.
+    pub const fn from_transition_table(table: [u16; 10]) -> Self {
+        let mut rules = Rules::new(Order::Custom);
+        rules.transitions = table;
+        rules
+    }
+
+    /// Which way digits must run, or `Order::Custom` for rules built from a transition table.
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    /// Whether `next` may come just after `digit`, reading left to right.
+    fn allows(&self, digit: u8, next: u8) -> bool {
+        self.transitions[digit as usize] & (1 << next) != 0
+    }
+
+    /// Also rejects any password with fewer than `min_distinct_digits` different digits.
+    pub fn with_min_distinct_digits(mut self, min_distinct_digits: u32) -> Self {
+        self.min_distinct_digits = min_distinct_digits;
+        self
+    }
+
+    /// Requires a run of exactly `run_length` identical digits instead of a pair.
+    pub fn with_run_length(mut self, run_length: usize) -> Self {
+        assert!(run_length > 0);
+        self.run_length = run_length;
+        self
+    }
+
+    /// Also rejects any password containing `digit`.
+    pub fn forbid(mut self, digit: u8) -> Self {
+        assert!(digit < 10);
+        self.forbidden_digits |= 1 << digit;
+        self
+    }
+
+    pub fn forbids(&self, digit: u8) -> bool {
+        self.forbidden_digits & (1 << digit) != 0
+    }
+}
+
+/// The puzzle's rules.
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::new(Order::NonDecreasing)
+    }
+}
+
+pub trait Password {
+    fn is_valid_password(&self) -> bool {
+        self.is_valid_under(Rules::default())
+    }
+
+    fn is_valid_under(&self, rules: Rules) -> bool;
+}
+
+/// `10^pos` for every digit position `make_digits` looks at.
+const POWERS_OF_TEN: [u64; 8] = [1, 10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+#[derive(Debug)]
+pub struct SillyNumber {
+    pub number: u64,
+    digits: Vec<u8>,
+}
+
+impl SillyNumber {
+    pub fn new(number: u64) -> Self {
+        let digits = Self::make_digits(number);
+
+        SillyNumber {
+            number,
+            digits,
+        }
+    }
+
+    fn make_digits(number: u64) -> Vec<u8> {
+        let mut digits = Vec::<u8>::with_capacity(6);
+        for pos in 0..6 {
+            let digit = Self::get_digit_at(number, pos);
+            digits.push(digit);
+        }
+
+        // This better only have six digits
+        assert!(Self::get_digit_at(number, 7) == 0);
+        digits
+    }
+
+    fn get_digit_at(number: u64, pos: usize) -> u8 {
+        let digit = number / POWERS_OF_TEN[pos] % 10;
+        let digit: u8 = digit as u8;
+        digit
+    }
+
+    pub fn increment(&mut self) {
+        self.number += 1;
+
+        // Only the trailing nines change, so carry through those rather than redoing every digit
+        for digit in self.digits.iter_mut() {
+            if *digit < 9 {
+                *digit += 1;
+                return;
+            }
+            *digit = 0;
+        }
+        self.digits = Self::make_digits(self.number);
+    }
+
+    fn decrement(&mut self) {
+        self.number -= 1;
+        self.digits = Self::make_digits(self.number);
+    }
+
+    /// Increments, returning the new number and whether it is valid under `rules`.
+    pub fn advance(&mut self, rules: Rules) -> (u64, bool) {
+        self.increment();
+        (self.number, self.is_valid_under(rules))
+    }
+
+    /// Increments, then jumps past any numbers that `rules` rule out.
+    pub fn increment_skipping(&mut self, rules: Rules) {
+        self.increment();
+        self.skip_to_candidate(rules);
+    }
+
+    /// Jumps forward to the nearest number whose digits are in order and allowed by `rules`.
+    pub fn skip_to_candidate(&mut self, rules: Rules) {
+        loop {
+            match rules.order() {
+                Order::NonDecreasing => self.skip_to_non_decreasing(),
+                Order::Mirror => self.skip_to_non_increasing(),
+                Order::Custom => (),
+            }
+
+            // Every number sharing the digits down to a forbidden one is also ruled
+            // out, so move on to the next value of that prefix and reorder from there.
+            let forbidden_pos = (0..self.digits.len()).rev()
+                .find(|pos| rules.forbids(self.digits[*pos]));
+            match forbidden_pos {
+                Some(pos) => {
+                    let place = 10_u64.pow(pos as u32);
+                    self.number = (self.number / place + 1) * place;
+                    self.digits = Self::make_digits(self.number);
+                },
+                None => return,
+            }
+        }
+    }
+
+    /// Jumps back to the nearest number at or below this one whose digits are in
+    /// order and allowed by `rules`, returning false if there isn't one.
+    fn skip_back_to_candidate(&mut self, rules: Rules) -> bool {
+        loop {
+            match rules.order() {
+                Order::NonDecreasing => self.skip_back_to_non_decreasing(),
+                Order::Mirror => self.skip_back_to_non_increasing(),
+                Order::Custom => (),
+            }
+
+            // Every number sharing the digits down to a forbidden one is also ruled
+            // out, so move to the largest number below that prefix and reorder from there.
+            let forbidden_pos = (0..self.digits.len()).rev()
+                .find(|pos| rules.forbids(self.digits[*pos]));
+            match forbidden_pos {
+                Some(pos) => {
+                    let below_prefix = self.number - self.number % 10_u64.pow(pos as u32);
+                    match below_prefix.checked_sub(1) {
+                        Some(number) => {
+                            self.number = number;
+                            self.digits = Self::make_digits(self.number);
+                        },
+                        None => return false,
+                    }
+                },
+                None => return true,
+            }
+        }
+    }
+
+    /// Once a digit is larger than the one after it, the largest smaller number in
+    /// order lowers that digit, then any before it left larger, and fills the rest with nines.
+    fn skip_back_to_non_decreasing(&mut self) {
+        let top = self.digits.len() - 1;
+        let mut digits = self.digits.clone();
+        for pos in (0..top).rev() {
+            if digits[pos] < digits[pos + 1] {
+                let mut lowered = pos + 1;
+                digits[lowered] -= 1;
+                while lowered < top && digits[lowered + 1] > digits[lowered] {
+                    lowered += 1;
+                    digits[lowered] -= 1;
+                }
+                for digit in &mut digits[..lowered] {
+                    *digit = 9;
+                }
+                self.number = Self::from_digits(&digits);
+                self.digits = digits;
+                return;
+            }
+        }
+    }
+
+    /// Once a digit is smaller than the one after it, the largest smaller number in
+    /// order repeats the digit before it the rest of the way.
+    fn skip_back_to_non_increasing(&mut self) {
+        let mut digits = self.digits.clone();
+        for pos in (0..digits.len() - 1).rev() {
+            if digits[pos] > digits[pos + 1] {
+                let fill = digits[pos + 1];
+                for digit in &mut digits[..=pos] {
+                    *digit = fill;
+                }
+                self.number = Self::from_digits(&digits);
+                self.digits = digits;
+                return;
+            }
+        }
+    }
+
+    /// The number with these digits, least significant first.
+    fn from_digits(digits: &[u8]) -> u64 {
+        digits.iter().rev().fold(0, |number, digit| number * 10 + *digit as u64)
+    }
+
+    /// Once a digit is smaller than the one before it, every number up to the
+    /// point where the remaining digits all repeat the larger one is invalid.
+    fn skip_to_non_decreasing(&mut self) {
+        // Digits are stored least significant first, so walk them backwards
+        for pos in (0..self.digits.len() - 1).rev() {
+            let previous = self.digits[pos + 1];
+            if self.digits[pos] < previous {
+                let high_part = self.number - self.number % 10_u64.pow(pos as u32 + 1);
+                let mut low_part = 0;
+                for fill_pos in 0..=pos {
+                    low_part += previous as u64 * 10_u64.pow(fill_pos as u32);
+                }
+                self.number = high_part + low_part;
+                self.digits = Self::make_digits(self.number);
+                return;
+            }
+        }
+    }
+
+    /// Once a digit is larger than the one before it, every number until the
+    /// digit before it rolls over is invalid. Rolling over can break the order
+    /// further up, so keep going until no digit is out of place.
+    fn skip_to_non_increasing(&mut self) {
+        let mut pos = self.digits.len() - 1;
+        while pos > 0 {
+            pos -= 1;
+            if self.digits[pos] > self.digits[pos + 1] {
+                let place = 10_u64.pow(pos as u32 + 1);
+                self.number = (self.number / place + 1) * place;
+                self.digits = Self::make_digits(self.number);
+                pos = self.digits.len() - 1;
+            }
+        }
+    }
+
+    /// Whether every digit is ordered relative to the one before it according to `order`.
+    pub fn is_ordered(&self, order: Order) -> bool {
+        self.digits.windows(2).all(|pair| !order.out_of_order(pair[0], pair[1]))
+    }
+
+    fn iter(&self) -> SillyNumberIter<'_> {
+        SillyNumberIter {
+            digits: &self.digits,
+            pos: 0,
+        }
+    }
+}
+
+impl Index<usize> for SillyNumber {
+    type Output = u8;
+
+    fn index(&self, pos: usize) -> &Self::Output {
+        &self.digits[pos]
+    }
+}
+
+struct SillyNumberIter<'a> {
+    digits: &'a Vec<u8>,
+    pos: usize,
+}
+
+impl<'a> Iterator for SillyNumberIter<'a> {
+    type Item = u8;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.digits.len() {
+            None
+        } else {
+            self.pos += 1;
+            Some(self.digits[self.pos-1])
+        }
+    }
+}
+
+impl Password for SillyNumber {
+    fn is_valid_under(&self, rules: Rules) -> bool {
+        self.rejection(rules).is_none()
+    }
+}
+
+impl SillyNumber {
+    /// Whether this is a password under part 1 and part 2 of the puzzle, checked in one pass.
+    fn puzzle_parts(&self) -> (bool, bool) {
+        if !self.is_ordered(Order::NonDecreasing) {
+            return (false, false);
+        }
+
+        let mut any_run = false;
+        let mut exact_pair = false;
+        let mut length_of_run = 1;
+        for pair in self.digits.windows(2) {
+            if pair[0] == pair[1] {
+                length_of_run += 1;
+                any_run = true;
+            } else {
+                exact_pair |= length_of_run == 2;
+                length_of_run = 1;
+            }
+        }
+        exact_pair |= length_of_run == 2;
+
+        (any_run, exact_pair)
+    }
+
+    /// The first rule this number breaks, if any.
+    pub fn rejection(&self, rules: Rules) -> Option<Rejection> {
+        let mut last_digit: Option<u8> = None;
+        let mut pair_found = false;
+        let mut length_of_run = 0;
+        // Bit `d` is set once digit `d` has been seen
+        let mut seen_digits: u16 = 0;
+
+        for digit in self.iter() {
+            if rules.forbids(digit) {
+                return Some(Rejection::ForbiddenDigit(digit));
+            }
+            seen_digits |= 1 << digit;
+
+            match last_digit {
+                Some(last_digit) if !rules.allows(digit, last_digit) => {
+                    return Some(Rejection::OutOfOrder);
+                },
+                Some(last_digit) if digit == last_digit => length_of_run += 1,
+                _ => {
+                    // A valid, different digit
+                    if !pair_found {
+                        pair_found = length_of_run == rules.run_length;
+                    }
+                    length_of_run = 1;
+                },
+            }
+            last_digit = Some(digit);
+        }
+
+        // We may end on a pair
+        if !pair_found && length_of_run != rules.run_length {
+            Some(Rejection::NoExactRun)
+        } else if seen_digits.count_ones() < rules.min_distinct_digits {
+            Some(Rejection::TooFewDistinctDigits)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiple_pairs() {
+        assert!(SillyNumber::new(112233).is_valid_password());
+        assert!(SillyNumber::new(125599).is_valid_password());
+        assert!(SillyNumber::new(115699).is_valid_password());
+    }
+
+    #[test]
+    fn test_run() {
+        assert!(!SillyNumber::new(999999).is_valid_password());
+        assert!(!SillyNumber::new(123444).is_valid_password());
+    }
+
+    #[test]
+    fn test_run_with_pair() {
+        assert!(SillyNumber::new(222559).is_valid_password());
+        assert!(SillyNumber::new(111199).is_valid_password());
+        assert!(SillyNumber::new(112222).is_valid_password());
+    }
+
+    #[test]
+    fn test_decreasing_digit() {
+        assert!(!SillyNumber::new(221555).is_valid_password());
+    }
+
+    #[test]
+    fn test_increment_skipping() {
+        let mut silly_number = SillyNumber::new(197487);
+        silly_number.increment_skipping(Rules::default());
+        assert_eq!(silly_number.number, 199999);
+
+        silly_number.increment_skipping(Rules::default());
+        assert_eq!(silly_number.number, 222222);
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut silly_number = SillyNumber::new(111119);
+        assert_eq!(silly_number.advance(Rules::default()), (111120, false));
+        assert_eq!(silly_number.advance(Rules::default()), (111121, false));
+        assert_eq!(silly_number.advance(Rules::default()), (111122, true));
+        assert_eq!(silly_number.advance(Rules::default().forbid(2)), (111123, false));
+        assert_eq!(silly_number.number, 111123);
+
+        let mut silly_number = SillyNumber::new(654432);
+        assert_eq!(silly_number.advance(Rules::new(Order::Mirror)), (654433, true));
+    }
+
+    #[test]
+    fn test_first_candidate_skips_decreasing_lower() {
+        assert!(!SillyNumber::new(197487).is_ordered(Order::NonDecreasing));
+
+        let silly_number = first_candidate(197487, Rules::default());
+        assert_eq!(silly_number.number, 199999);
+        assert!(silly_number.is_ordered(Order::NonDecreasing));
+
+        assert_eq!(first_candidate(223450, Rules::default()).number, 223455);
+        assert_eq!(first_candidate(123456, Rules::default()).number, 123456);
+    }
+
+    #[test]
+    fn test_mirror_mode() {
+        assert!(SillyNumber::new(654433).is_valid_under(Rules::new(Order::Mirror)));
+        assert!(SillyNumber::new(998710).is_valid_under(Rules::new(Order::Mirror)));
+        assert!(!SillyNumber::new(654443).is_valid_under(Rules::new(Order::Mirror)));
+        assert!(!SillyNumber::new(654433).is_valid_password());
+        assert!(!SillyNumber::new(112233).is_valid_under(Rules::new(Order::Mirror)));
+    }
+
+    #[test]
+    fn test_mirror_skip_ahead() {
+        assert_eq!(first_candidate(557000, Rules::new(Order::Mirror)).number, 600000);
+        assert_eq!(first_candidate(653999, Rules::new(Order::Mirror)).number, 654000);
+
+        let naive = count_valid_naive(100000, 999999, Rules::new(Order::Mirror));
+        assert_eq!(count_valid_skip_ahead(100000, 999999, Rules::new(Order::Mirror)), naive);
+    }
+
+    #[test]
+    fn test_forbidden_digits() {
+        let mirror = Rules::new(Order::Mirror);
+        let no_zeros = mirror.forbid(0);
+
+        assert!(SillyNumber::new(998810).is_valid_under(mirror));
+        assert!(!SillyNumber::new(998810).is_valid_under(no_zeros));
+        assert_eq!(count_valid_naive(998800, 998899, mirror), 45);
+        assert_eq!(count_valid_naive(998800, 998899, no_zeros), 36);
+        assert_eq!(count_valid_skip_ahead(998800, 998899, no_zeros), 36);
+    }
+
+    #[test]
+    fn test_forbidden_digits_skip_ahead() {
+        let no_twos = Rules::default().forbid(2);
+        assert_eq!(first_candidate(222222, no_twos).number, 333333);
+        assert_eq!(first_candidate(123456, no_twos).number, 133333);
+
+        let naive = count_valid_naive(197487, 673251, no_twos);
+        assert_eq!(count_valid_skip_ahead(197487, 673251, no_twos), naive);
+    }
+
+    #[test]
+    fn test_run_length() {
+        let triples = Rules::default().with_run_length(3);
+
+        assert!(SillyNumber::new(112223).is_valid_under(triples));
+        assert!(SillyNumber::new(123444).is_valid_under(triples));
+        assert!(SillyNumber::new(111999).is_valid_under(triples));
+        assert!(!SillyNumber::new(112233).is_valid_under(triples));
+        assert!(!SillyNumber::new(111122).is_valid_under(triples));
+        assert!(!SillyNumber::new(111111).is_valid_under(triples));
+
+        // 111abc with 2 <= a <= b <= c, since a 1 there makes the run of ones too long
+        assert_eq!(count_valid_naive(111111, 111999, triples), 120);
+        assert_eq!(count_valid_skip_ahead(111111, 111999, triples), 120);
+    }
+
+    #[test]
+    fn test_last_valid_before() {
+        assert_eq!(last_valid_before(673251, Rules::default()), Some(669999));
+        assert_eq!(last_valid_before(669999, Rules::default()), Some(668999));
+        assert_eq!(last_valid_before(200000, Rules::default()), Some(188999));
+        assert_eq!(last_valid_before(1, Rules::default()), None);
+
+        assert_eq!(last_valid_before(557000, Rules::new(Order::Mirror)), Some(555544));
+        assert_eq!(last_valid_before(300000, Rules::default().forbid(2)), Some(188999));
+    }
+
+    #[test]
+    fn test_last_valid_before_matches_scan() {
+        for rules in [Rules::default(), Rules::new(Order::Mirror), Rules::default().forbid(8).with_run_length(3)] {
+            for upper in [111111, 197487, 250000, 673251, 999999] {
+                let expected = (0..upper).rev().find(|number| SillyNumber::new(*number).is_valid_under(rules));
+                assert_eq!(last_valid_before(upper, rules), expected, "{} {:?}", upper, rules);
+            }
+        }
+    }
+
+    #[test]
+    fn test_valid_density() {
+        // Only 111122 is valid
+        assert_eq!(valid_density(111120, 111130, Rules::default()), 1.0 / 11.0);
+        assert_eq!(valid_density(111122, 111129, Rules::default()), 1.0 / 8.0);
+        // Every one of them starts with a run of four ones
+        assert_eq!(valid_density(111122, 111129, Rules::default().with_run_length(4)), 1.0);
+        assert_eq!(valid_density(111122, 111122, Rules::new(Order::Mirror)), 0.0);
+        assert_eq!(valid_density(5, 4, Rules::default()), 0.0);
+    }
+
+    #[test]
+    fn test_valid_passwords_desc() {
+        for rules in [Rules::default(), Rules::new(Order::Mirror), Rules::default().forbid(3).with_run_length(3)] {
+            let mut ascending = valid_passwords(197487, 673251, rules);
+            ascending.reverse();
+            let descending: Vec<u64> = valid_passwords_desc(197487, 673251, rules).collect();
+            assert_eq!(descending, ascending);
+        }
+
+        let largest: Vec<u64> = valid_passwords_desc(0, 999999, Rules::default()).take(3).collect();
+        assert_eq!(largest, vec![889999, 888899, 788999]);
+        assert_eq!(valid_passwords_desc(5, 4, Rules::default()).next(), None);
+    }
+
+    #[test]
+    fn test_min_distinct_digits() {
+        let three = Rules::default().with_min_distinct_digits(3);
+        assert_eq!(SillyNumber::new(112222).rejection(three), Some(Rejection::TooFewDistinctDigits));
+        assert_eq!(SillyNumber::new(112223).rejection(three), None);
+        assert_eq!(SillyNumber::new(111123).rejection(three), Some(Rejection::NoExactRun));
+
+        // 1122ab with 2 <= a <= b, where 112222 is the only one with two digits...
+        assert_eq!(count_valid_naive(112220, 112299, Rules::default()), 36);
+        assert_eq!(count_valid_naive(112220, 112299, three), 35);
+        // ...and four needs 3 <= a < b
+        assert_eq!(count_valid_naive(112220, 112299, Rules::default().with_min_distinct_digits(4)), 21);
+        assert_eq!(count_valid_skip_ahead(112220, 112299, three), 35);
+    }
+
+    #[test]
+    fn test_valid_bitset() {
+        let bits = valid_bitset(111120, 111130, Rules::default());
+
+        assert_eq!(bits.len(), 11);
+        assert!(bits.get(2)); // 111122
+        assert!(!bits.get(1)); // 111121
+        assert!(!bits.get(10)); // 111130
+        assert_eq!(bits.count_ones(), 1);
+
+        let bits = valid_bitset(197487, 673251, Rules::default());
+        assert_eq!(bits.count_ones(), 1126);
+    }
+
+    #[test]
+    fn test_rejection() {
+        let no_fives = Rules::default().forbid(5);
+        assert_eq!(SillyNumber::new(112233).rejection(no_fives), None);
+        assert_eq!(SillyNumber::new(125599).rejection(no_fives), Some(Rejection::ForbiddenDigit(5)));
+        assert_eq!(SillyNumber::new(221666).rejection(no_fives), Some(Rejection::OutOfOrder));
+        assert_eq!(SillyNumber::new(123444).rejection(no_fives), Some(Rejection::NoExactRun));
+    }
+
+    #[test]
+    fn test_diagnose_never_skips_valid() {
+        for rules in [Rules::default(), Rules::new(Order::Mirror), Rules::default().forbid(2)] {
+            // The numbers the skip-ahead actually stops on
+            let mut candidates = Vec::new();
+            let mut silly_number = first_candidate(197487, rules);
+            while silly_number.number <= 673251 {
+                candidates.push(silly_number.number);
+                silly_number.increment_skipping(rules);
+            }
+
+            let mut valid = 0;
+            for (number, outcome) in diagnose(197487, 673251, rules) {
+                let checked = candidates.binary_search(&number).is_ok();
+                match outcome {
+                    Outcome::Valid => {
+                        assert!(checked, "{} is valid but was skipped", number);
+                        valid += 1;
+                    },
+                    Outcome::Skipped(_) => assert!(!checked),
+                    Outcome::Invalid(_) => assert!(checked),
+                }
+            }
+            assert_eq!(valid, count_valid_naive(197487, 673251, rules));
+        }
+    }
+
+    #[test]
+    fn test_diagnose_reasons() {
+        let outcomes: Vec<_> = diagnose(111119, 111122, Rules::default()).collect();
+        assert_eq!(outcomes, vec![
+            (111119, Outcome::Invalid(Rejection::NoExactRun)),
+            (111120, Outcome::Skipped(Rejection::OutOfOrder)),
+            (111121, Outcome::Skipped(Rejection::OutOfOrder)),
+            (111122, Outcome::Valid),
+        ]);
+    }
+
+    #[test]
+    fn test_valid_passwords_parallel() {
+        let sequential = valid_passwords(197487, 673251, Rules::default());
+        assert_eq!(sequential.len(), 1126);
+        assert!(sequential.windows(2).all(|pair| pair[0] < pair[1]));
+
+        for workers in [1, 3, 4, 7] {
+            assert_eq!(valid_passwords_parallel(197487, 673251, workers, Rules::default()), sequential);
+        }
+        assert!(valid_passwords_parallel(5, 4, 4, Rules::default()).is_empty());
+    }
+
+    #[test]
+    fn test_transition_table() {
+        for order in [Order::NonDecreasing, Order::Mirror] {
+            let from_table = Rules::from_transition_table(order.transition_table());
+            assert_eq!(from_table.order(), Order::Custom);
+            assert_eq!(count_valid_naive(100000, 399999, from_table), count_valid_naive(100000, 399999, Rules::new(order)));
+        }
+
+        // Each digit may only repeat or go up by one
+        let mut table = [0; 10];
+        for (digit, entry) in table.iter_mut().enumerate() {
+            *entry = 0b11 << digit;
+        }
+        let steps = Rules::from_transition_table(table);
+        assert!(SillyNumber::new(112234).is_valid_under(steps));
+        assert_eq!(SillyNumber::new(112244).rejection(steps), Some(Rejection::OutOfOrder));
+        assert_eq!(SillyNumber::new(123456).rejection(steps), Some(Rejection::NoExactRun));
+        assert_eq!(count_valid_skip_ahead(100000, 999999, steps), count_valid_naive(100000, 999999, steps));
+    }
+
+    #[test]
+    fn test_cached_digits() {
+        let mut silly_number = SillyNumber::new(99990);
+        while silly_number.number < 200010 {
+            silly_number.increment();
+
+            let digits: Vec<u8> = (0..6)
+                .map(|pos| (silly_number.number / 10_u64.pow(pos) % 10) as u8)
+                .collect();
+            assert_eq!(silly_number.digits, digits);
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        // 123444 only counts for part 1, 123455 for both, and 123445 to 123449 for both
+        assert_eq!(solve(123440, 123460), (7, 6));
+        // Only 111122 has a run of exactly two, but all 17 ordered numbers repeat a digit
+        assert_eq!(solve(111110, 111130), (17, 1));
+
+        assert_eq!(solve(197487, 673251).1, count_valid_skip_ahead(197487, 673251, Rules::default()));
+    }
+
+    #[test]
+    fn test_scans_agree() {
+        let naive = count_valid_naive(197487, 673251, Rules::default());
+        assert_eq!(naive, 1126);
+        assert_eq!(count_valid_skip_ahead(197487, 673251, Rules::default()), naive);
+        assert_eq!(count_valid_parallel(197487, 673251, 4, Rules::default()), naive);
+    }
+}
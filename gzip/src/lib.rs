@@ -0,0 +1,359 @@
+/// The two bytes every gzip member starts with.
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+const FLAG_HCRC: u8 = 1 << 1;
+const FLAG_EXTRA: u8 = 1 << 2;
+const FLAG_NAME: u8 = 1 << 3;
+const FLAG_COMMENT: u8 = 1 << 4;
+
+/// The shortest match length for each length code from 257, before its extra bits.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// The shortest distance back for each distance code, before its extra bits.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// The order a dynamic block lists the code lengths of its code length alphabet in.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Whether `bytes` look like gzip data.
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Decompresses every gzip member in `bytes`, one after the other.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut reader = BitReader::new(bytes);
+    let mut output = Vec::new();
+    loop {
+        let start = output.len();
+        reader.read_header()?;
+        inflate(&mut reader, &mut output)?;
+
+        reader.align();
+        let crc = reader.read_u32()?;
+        let size = reader.read_u32()?;
+        if crc != crc32(&output[start..]) {
+            return Err("gzip checksum mismatch");
+        }
+        if size != (output.len() - start) as u32 {
+            return Err("gzip length mismatch");
+        }
+
+        if reader.is_done() {
+            return Ok(output);
+        }
+    }
+}
+
+/// Decodes one deflate stream from `reader` onto the end of `output`.
+fn inflate(reader: &mut BitReader, output: &mut Vec<u8>) -> Result<(), &'static str> {
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let len = reader.read_u16()?;
+                let nlen = reader.read_u16()?;
+                if len != !nlen {
+                    return Err("corrupt stored block length");
+                }
+                output.extend_from_slice(reader.take(len as usize)?);
+            },
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                inflate_block(reader, output, &lengths, &distances)?;
+            },
+            2 => {
+                let (lengths, distances) = dynamic_codes(reader)?;
+                inflate_block(reader, output, &lengths, &distances)?;
+            },
+            _ => return Err("invalid deflate block type"),
+        }
+
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+/// Decodes literals and back references until the end of block symbol.
+fn inflate_block(reader: &mut BitReader, output: &mut Vec<u8>, lengths: &Huffman, distances: &Huffman)
+    -> Result<(), &'static str>
+{
+    loop {
+        let symbol = lengths.decode(reader)? as usize;
+        if symbol < 256 {
+            output.push(symbol as u8);
+            continue;
+        } else if symbol == 256 {
+            return Ok(());
+        }
+
+        let code = symbol - 257;
+        if code >= LENGTH_BASE.len() {
+            return Err("invalid length code");
+        }
+        let length = LENGTH_BASE[code] as usize + reader.bits(LENGTH_EXTRA[code])? as usize;
+
+        let code = distances.decode(reader)? as usize;
+        if code >= DISTANCE_BASE.len() {
+            return Err("invalid distance code");
+        }
+        let distance = DISTANCE_BASE[code] as usize + reader.bits(DISTANCE_EXTRA[code])? as usize;
+        if distance > output.len() {
+            return Err("distance reaches before the start of the output");
+        }
+
+        // The match may overlap what it is copying, so go a byte at a time
+        let from = output.len() - distance;
+        for index in from..from + length {
+            output.push(output[index]);
+        }
+    }
+}
+
+/// The codes every fixed Huffman block uses.
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+/// Reads the codes a dynamic Huffman block describes in its header.
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), &'static str> {
+    let length_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0; 19];
+    for symbol in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[*symbol] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::<u8>::with_capacity(length_count + distance_count);
+    while lengths.len() < length_count + distance_count {
+        let (length, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("repeated code length with nothing before it")?;
+                (previous, 3 + reader.bits(2)?)
+            },
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths.len() > length_count + distance_count {
+        return Err("code lengths run past the end of the table");
+    }
+
+    let (length_lengths, distance_lengths) = lengths.split_at(length_count);
+    Ok((Huffman::new(length_lengths), Huffman::new(distance_lengths)))
+}
+
+/// A canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// How many symbols have a code of each length.
+    counts: [u16; 16],
+    /// Symbols ordered by code length, then by value.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code where symbol `n` has a code `lengths[n]` bits long, or none if that is 0.
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16)
+            .filter(|symbol| lengths[*symbol as usize] != 0)
+            .collect();
+        symbols.sort_by_key(|symbol| lengths[*symbol as usize]);
+
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, &'static str> {
+        // Codes of each length follow on from the last code of the length before
+        let mut code: u32 = 0;
+        let mut first: u32 = 0;
+        let mut index: u32 = 0;
+        for count in self.counts.iter().skip(1) {
+            code |= reader.bits(1)?;
+            let count = *count as u32;
+            if code < first + count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code")
+    }
+}
+
+/// Reads bits least significant first, as deflate packs them.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Bits of `bytes[pos]` already read.
+    bit: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, pos: 0, bit: 0 }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn bits(&mut self, count: u8) -> Result<u32, &'static str> {
+        let mut value = 0;
+        for shift in 0..count {
+            let byte = *self.bytes.get(self.pos).ok_or("unexpected end of gzip data")?;
+            value |= (((byte >> self.bit) & 1) as u32) << shift;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.align();
+            }
+        }
+        Ok(value)
+    }
+
+    /// Skips to the start of the next byte, unless already there.
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.pos += 1;
+            self.bit = 0;
+        }
+    }
+
+    /// The next `count` bytes, which must start on a byte boundary.
+    fn take(&mut self, count: usize) -> Result<&'a [u8], &'static str> {
+        let taken = self.bytes.get(self.pos..self.pos + count).ok_or("unexpected end of gzip data")?;
+        self.pos += count;
+        Ok(taken)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, &'static str> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, &'static str> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Skips past a zero terminated string.
+    fn skip_string(&mut self) -> Result<(), &'static str> {
+        while self.read_u8()? != 0 {}
+        Ok(())
+    }
+
+    /// Checks and skips past a gzip member header.
+    fn read_header(&mut self) -> Result<(), &'static str> {
+        if self.take(2)? != MAGIC {
+            return Err("not gzip data");
+        }
+        if self.read_u8()? != 8 {
+            return Err("unsupported gzip compression method");
+        }
+        let flags = self.read_u8()?;
+        // Modification time, extra flags, and OS
+        self.take(6)?;
+
+        if flags & FLAG_EXTRA != 0 {
+            let len = self.read_u16()?;
+            self.take(len as usize)?;
+        }
+        if flags & FLAG_NAME != 0 {
+            self.skip_string()?;
+        }
+        if flags & FLAG_COMMENT != 0 {
+            self.skip_string()?;
+        }
+        if flags & FLAG_HCRC != 0 {
+            self.read_u16()?;
+        }
+        Ok(())
+    }
+}
+
+/// The CRC-32 gzip stores to check the decompressed data.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_block() {
+        let bytes = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x03, 0x01, 0x05, 0x00, 0xfa,
+            0xff, 0x52, 0x38, 0x2c, 0x55, 0x35, 0x4e, 0x69, 0x46, 0x11, 0x05, 0x00, 0x00, 0x00,
+        ];
+        assert!(is_gzip(&bytes));
+        assert_eq!(decompress(&bytes), Ok(b"R8,U5".to_vec()));
+    }
+
+    #[test]
+    fn test_fixed_block_with_name() {
+        // "R8,U5,R8,U5" repeats itself through a back reference
+        let mut bytes = vec![
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x0b, 0xb2, 0xd0, 0x09,
+            0x35, 0xd5, 0x09, 0x02, 0x91, 0x00, 0x3b, 0x4a, 0x23, 0x14, 0x0b, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decompress(&bytes), Ok(b"R8,U5,R8,U5".to_vec()));
+
+        bytes[3] = FLAG_NAME;
+        bytes.splice(10..10, b"wires\0".iter().copied());
+        assert_eq!(decompress(&bytes), Ok(b"R8,U5,R8,U5".to_vec()));
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let bytes = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x03, 0x01, 0x05, 0x00, 0xfa,
+            0xff, 0x52, 0x38, 0x2c, 0x55, 0x36, 0x4e, 0x69, 0x46, 0x11, 0x05, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decompress(&bytes), Err("gzip checksum mismatch"));
+        assert_eq!(decompress(&bytes[..20]), Err("unexpected end of gzip data"));
+        assert!(!is_gzip(b"R8,U5"));
+    }
+}
@@ -0,0 +1,2977 @@
+use std::collections::{HashSet, VecDeque};
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::num::{ParseIntError, TryFromIntError};
+use std::ops;
+use std::str::FromStr;
+
+
+/// Parses and runs `src`, feeding it `inputs` and returning everything it outputs.
+pub fn run_program_str(src: &str, inputs: &[Word]) -> Result<Vec<Word>, ExecError> {
+    let mut program: IntcodeProgram = src.parse()?;
+    let mut cpu = Cpu::with_inputs(inputs);
+    cpu.execute(&mut program)?;
+
+    Ok(cpu.outputs().iter().map(|output| output.0).collect())
+}
+
+/// Parses and runs `src` on `inputs`, reporting malformed programs and failed runs as
+/// errors rather than panicking.
+///
+/// Unlike `IntcodeProgram::from`, a program that doesn't parse is an `ExecError::InvalidProgram`.
+/// Only a custom `InstructionSet` that panics itself can still panic.
+pub fn run_catching(src: &str, inputs: &[Word]) -> Result<RunResult, ExecError> {
+    let mut program: IntcodeProgram = src.parse()?;
+    Cpu::with_inputs(inputs).execute(&mut program)
+}
+
+/// Parses and runs `src`, feeding it `inputs` and returning only the last thing it outputs.
+pub fn last_output(src: &str, inputs: &[Word]) -> Result<Option<Word>, ExecError> {
+    Ok(run_program_str(src, inputs)?.last().copied())
+}
+
+/// Runs `producer` on `producer_inputs` until it halts, then feeds everything it
+/// output to `consumer` as input, returning the consumer's outputs.
+///
+/// Outputs the consumer never reads are ignored, and a consumer that wants more
+/// than the producer gave it fails with `ExecError::InputExhausted`.
+pub fn pipe(producer: &str, producer_inputs: &[Word], consumer: &str) -> Result<Vec<Word>, ExecError> {
+    let piped = run_program_str(producer, producer_inputs)?;
+    run_program_str(consumer, &piped)
+}
+
+/// Runs `src` on `inputs` and panics with a description of the first difference
+/// if its outputs aren't exactly `reference_outputs`.
+pub fn assert_same_outputs(src: &str, inputs: &[Word], reference_outputs: &[Word]) {
+    if let Some(mismatch) = output_mismatch(src, inputs, reference_outputs) {
+        panic!("{}", mismatch);
+    }
+}
+
+/// Checks every `(src, inputs, reference_outputs)` fixture, panicking with all of the mismatches at once.
+pub fn assert_all_same_outputs(fixtures: &[(&str, &[Word], &[Word])]) {
+    let mismatches: Vec<String> = fixtures.iter()
+        .enumerate()
+        .filter_map(|(index, (src, inputs, reference_outputs))| {
+            output_mismatch(src, inputs, reference_outputs)
+                .map(|mismatch| format!("fixture {}: {}", index, mismatch))
+        })
+        .collect();
+    if !mismatches.is_empty() {
+        panic!("{} of {} fixtures differ:\n{}", mismatches.len(), fixtures.len(), mismatches.join("\n"));
+    }
+}
+
+/// Describes how the outputs of running `src` on `inputs` differ from `reference_outputs`, if they do.
+fn output_mismatch(src: &str, inputs: &[Word], reference_outputs: &[Word]) -> Option<String> {
+    let outputs = match run_program_str(src, inputs) {
+        Ok(outputs) => outputs,
+        Err(err) => return Some(format!("program failed: {}", err)),
+    };
+
+    let differing = outputs.iter()
+        .zip(reference_outputs)
+        .position(|(output, reference)| output != reference);
+    let index = match differing {
+        Some(index) => index,
+        // One is a prefix of the other
+        None if outputs.len() != reference_outputs.len() => outputs.len().min(reference_outputs.len()),
+        None => return None,
+    };
+    Some(format!("outputs differ first at index {}: got {:?}, expected {:?}\n  got:      {:?}\n  expected: {:?}",
+        index, outputs.get(index), reference_outputs.get(index), outputs, reference_outputs))
+}
+
+/// Runs a copy of `program` with `patches` applied and returns the word left at `address`.
+pub fn run_and_read(program: &IntcodeProgram, patches: &[(Address, Word)], address: Address) -> Result<Word, ExecError> {
+    let mut program_copy = program.clone();
+    program_copy.apply_patches(patches)?;
+    Cpu::new().execute(&mut program_copy)?;
+
+    Ok(Cpu::read(&program_copy, address)?.0)
+}
+
+/// Runs `original` with `patches` applied in `scratch`, which is overwritten with a copy
+/// of the program first and left holding the final memory.
+///
+/// Reusing one `scratch` across many runs avoids allocating a new copy each time.
+pub fn run_into(original: &IntcodeProgram, patches: &[(Address, Word)], scratch: &mut Vec<Word>)
+    -> Result<RunResult, ExecError>
+{
+    scratch.clone_from(&original.raw_program);
+    let mut memory = IntcodeProgram::from_vec(std::mem::take(scratch));
+    let result = memory.apply_patches(patches).and_then(|()| Cpu::new().execute(&mut memory));
+    *scratch = memory.raw_program;
+    result
+}
+
+/// Formats `value` in decimal alongside its two's complement hex form, as wide as a `Word`.
+pub fn format_value(value: Word) -> String {
+    format!("{} (0x{:0width$x})", value, value, width = (Word::BITS / 4) as usize)
+}
+
+/// The base used to write and read Intcode words as text.
+///
+/// Only the text changes: a word is the same `Word` whatever it was written in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hex,
+}
+
+impl Radix {
+    pub fn base(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hex => 16,
+        }
+    }
+
+    /// Writes `value` in this base, with a leading `-` if negative and no prefix.
+    pub fn format(&self, value: Word) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let magnitude = value.unsigned_abs();
+        match self {
+            Radix::Binary => format!("{}{:b}", sign, magnitude),
+            Radix::Octal => format!("{}{:o}", sign, magnitude),
+            Radix::Decimal => format!("{}{}", sign, magnitude),
+            Radix::Hex => format!("{}{:x}", sign, magnitude),
+        }
+    }
+
+    /// Reads a word written by `format`.
+    pub fn parse(&self, s: &str) -> Result<Word, ParseIntError> {
+        Word::from_str_radix(s, self.base())
+    }
+}
+
+/// Looks for a `Halt` the program can reach from address 0, without running it.
+///
+/// This only follows the paths the program's code visibly takes, so a program that
+/// rewrites its own code on the way may halt anyway. `None` means no `Halt` was found.
+pub fn find_reachable_halt(program: &dyn Memory) -> Option<Address> {
+    let mut halt = None;
+    walk_code(program, |address, decoded| match decoded {
+        Ok(Instruction::Halt()) => {
+            halt = Some(address);
+            false
+        },
+        _ => true,
+    });
+    halt
+}
+
+/// Decodes every instruction reachable from address 0 without running any of them,
+/// failing on the first that would stop a run: an unknown opcode, a parameter that
+/// isn't an address, a write to an immediate parameter, or a write past where memory
+/// can grow to.
+///
+/// Like `find_reachable_halt`, this can't see code the program writes for itself.
+pub fn dry_run(program: &dyn Memory) -> Result<(), ExecError> {
+    let mut result = Ok(());
+    walk_code(program, |_, decoded| {
+        result = decoded.and_then(|instruction| match instruction.write_target() {
+            Some(Param::Position(target)) if target.0 >= program.capacity() =>
+                Err(ExecError::OutOfBounds { address: target }),
+            _ => Ok(()),
+        });
+        result.is_ok()
+    });
+    result
+}
+
+/// Lists the program as assembly, one line per instruction in order from address 0.
+///
+/// Parameters are written `#value` when immediate, `@address` in position mode, and
+/// `~offset` when relative. A word that doesn't start a whole instruction is listed
+/// as `DATA`, and the listing carries on from the word after it.
+pub fn disassemble(program: &IntcodeProgram) -> Vec<String> {
+    let param = |param: Param| match param {
+        Param::Immediate(value) => format!("#{}", value),
+        Param::Position(address) => format!("@{}", address),
+        Param::Relative(offset) => format!("~{}", offset),
+    };
+
+    let mut lines = Vec::new();
+    let mut address = Address(0);
+    while address.0 < program.len() {
+        let decoded = StandardInstructions.decode(address, program).ok()
+            .filter(|instruction| address.0 + instruction.width() <= program.len());
+        let text = match decoded {
+            Some(Instruction::Add(param_0, param_1, res)) =>
+                format!("ADD {} {} -> {}", param(param_0), param(param_1), param(res)),
+            Some(Instruction::Mult(param_0, param_1, res)) =>
+                format!("MULT {} {} -> {}", param(param_0), param(param_1), param(res)),
+            Some(Instruction::Input(res)) => format!("IN -> {}", param(res)),
+            Some(Instruction::Output(param_0)) => format!("OUT {}", param(param_0)),
+            Some(Instruction::JumpIfTrue(param_0, param_1)) => format!("JT {} {}", param(param_0), param(param_1)),
+            Some(Instruction::JumpIfFalse(param_0, param_1)) => format!("JF {} {}", param(param_0), param(param_1)),
+            Some(Instruction::LessThan(param_0, param_1, res)) =>
+                format!("LT {} {} -> {}", param(param_0), param(param_1), param(res)),
+            Some(Instruction::Equals(param_0, param_1, res)) =>
+                format!("EQ {} {} -> {}", param(param_0), param(param_1), param(res)),
+            Some(Instruction::AdjustBase(param_0)) => format!("ARB {}", param(param_0)),
+            Some(Instruction::Halt()) => "HALT".to_string(),
+            Some(Instruction::Custom { .. }) | None => format!("DATA {}", program.at(address)),
+        };
+        lines.push(format!("{:04}: {}", address, text));
+        address = address + decoded.map_or(1, |instruction| instruction.width());
+    }
+    lines
+}
+
+/// Decodes each instruction reachable from address 0 once, passing it to `visit`
+/// until `visit` returns false. Paths end at a `Halt` or anything that doesn't decode.
+fn walk_code<F>(program: &dyn Memory, mut visit: F)
+where
+    F: FnMut(Address, Result<Instruction, ExecError>) -> bool,
+{
+    let mut visited = vec![false; program.len()];
+    let mut pending = vec![Address(0)];
+    while let Some(address) = pending.pop() {
+        if let Some(seen) = visited.get_mut(address.0) {
+            if *seen {
+                continue;
+            }
+            *seen = true;
+        }
+
+        let decoded = StandardInstructions.decode(address, program);
+        let successors = match &decoded {
+            Ok(instruction) => instruction.successors(address),
+            Err(_) => Vec::new(),
+        };
+        if !visit(address, decoded) {
+            return;
+        }
+        pending.extend(successors);
+    }
+}
+
+/// An index into Intcode memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address(pub usize);
+
+/// The integer type a `Value` holds.
+///
+/// Normally `i64`. The `wide` feature makes it `i128` for programs whose arithmetic
+/// overflows that, at the cost of twice the memory per word and slower arithmetic.
+#[cfg(not(feature = "wide"))]
+pub type Word = i64;
+#[cfg(feature = "wide")]
+pub type Word = i128;
+
+/// A single word stored in Intcode memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Value(pub Word);
+
+/// The most words an `IntcodeProgram` grows to before accesses are out of bounds.
+pub const MAX_MEMORY: usize = 1 << 20;
+
+pub trait Memory {
+    fn read_at(&self, address: Address) -> Value;
+    fn write_at(&mut self, value: Value, address: Address);
+    fn len(&self) -> usize;
+
+    /// How many words can be addressed. Memory that grows reads as zero between
+    /// `len` and here, and grows when written to; by default it doesn't grow.
+    fn capacity(&self) -> usize {
+        self.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Overrides each `(address, value)` cell, typically before a run starts.
+    ///
+    /// Stops at the first patch past `capacity`, leaving the ones before it applied.
+    fn apply_patches(&mut self, patches: &[(Address, Word)]) -> Result<(), ExecError> {
+        for (address, value) in patches {
+            if address.0 >= self.capacity() {
+                return Err(ExecError::OutOfBounds { address: *address });
+            }
+            self.write_at(Value(*value), *address);
+        }
+        Ok(())
+    }
+}
+
+/// Reasons a program can fail to run to completion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecError {
+    /// There is no instruction at address 0 to start from.
+    EmptyProgram,
+    /// The program source contains a code that is not an integer.
+    InvalidProgram(ParseIntError),
+    /// An `Input` instruction ran with no input left to read.
+    InputExhausted,
+    /// The word at `address` is not an opcode this `Cpu` understands.
+    UnknownOpcode { opcode: Word, address: Address },
+    /// The parameter at `address` holds `value`, which cannot be used as a pointer.
+    InvalidAddress { value: Word, address: Address },
+    /// The program tried to access memory past its end.
+    OutOfBounds { address: Address },
+    /// The program ran `steps` instructions without halting, under `Cpu::execute_within`.
+    StepLimitExceeded { steps: u64 },
+    /// The instruction at `address` has an immediate parameter to write to.
+    ImmediateWriteTarget { address: Address },
+    /// A relative parameter's `offset` from the relative `base` is not a valid address.
+    InvalidRelativeAddress { base: Word, offset: Word },
+    /// An interactive input line was not an integer.
+    InvalidInput(String),
+    /// Reading interactive input or writing output failed.
+    Io(io::ErrorKind),
+    /// Bytes given to `CpuSnapshot::from_bytes` are not a serialized snapshot.
+    CorruptSnapshot,
+    /// The `Add` or `Mult` at `address` overflowed a `Word`, with checked arithmetic on.
+    Overflow { address: Address },
+    /// The instruction with `opcode` at `address` failed to access memory with `error`.
+    AtInstruction { address: Address, opcode: Word, error: Box<ExecError> },
+}
+
+impl ExecError {
+    /// The error underneath any `AtInstruction` context.
+    pub fn root_cause(&self) -> &ExecError {
+        match self {
+            ExecError::AtInstruction { error, .. } => error.root_cause(),
+            error => error,
+        }
+    }
+
+    /// Adds the instruction to errors that don't already say which one failed.
+    fn in_instruction(self, address: Address, opcode: Word) -> ExecError {
+        match self {
+            ExecError::InvalidAddress { .. } | ExecError::OutOfBounds { .. }
+            | ExecError::InvalidRelativeAddress { .. } =>
+                ExecError::AtInstruction { address, opcode, error: Box::new(self) },
+            error => error,
+        }
+    }
+}
+
+/// Why `IntcodeProgram::try_from_reader` couldn't read a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntcodeParseError {
+    /// Token `index`, counting from 0, is `text`, which is not a word.
+    InvalidToken { index: usize, text: String },
+    /// Reading the program failed.
+    Io(io::ErrorKind),
+    /// The program looked gzipped, but didn't decompress.
+    Gzip(&'static str),
+}
+
+/// The errors a `Cpu` can stop with, under the name other Intcode code knows them by.
+pub type CpuError = ExecError;
+
+/// What an `Input` instruction does once there is no input left.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InputPolicy {
+    /// Fail the run with `ExecError::InputExhausted`.
+    Error,
+    /// Stop the run cleanly with `HaltReason::InputEnded`.
+    Halt,
+    /// Read this value instead, as many times as asked.
+    Default(Word),
+}
+
+/// A parameter that an instruction reads from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Param {
+    /// Mode 0: the value is at this address.
+    Position(Address),
+    /// Mode 1: the parameter is the value itself.
+    Immediate(Value),
+    /// Mode 2: the value is at this offset from the `Cpu`'s relative base.
+    Relative(Word),
+}
+
+/// Shows positions as `&address`, immediates as `#value`, and relative offsets as `~offset`,
+/// padding to any given width.
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Param::Position(address) => {
+                write!(f, "&")?;
+                fmt::Display::fmt(address, f)
+            },
+            Param::Immediate(value) => {
+                write!(f, "#")?;
+                fmt::Display::fmt(value, f)
+            },
+            Param::Relative(offset) => {
+                write!(f, "~")?;
+                fmt::Display::fmt(offset, f)
+            },
+        }
+    }
+}
+
+/// Parameters an instruction writes to are never immediate; standard decoding fails
+/// with `ExecError::ImmediateWriteTarget` instead.
+pub enum Instruction {
+    Add(Param, Param, Param),
+    Mult(Param, Param, Param),
+    Input(Param),
+    Output(Param),
+    /// Jumps to the second parameter if the first isn't zero.
+    JumpIfTrue(Param, Param),
+    /// Jumps to the second parameter if the first is zero.
+    JumpIfFalse(Param, Param),
+    /// Writes 1 if the first parameter is less than the second, and 0 otherwise.
+    LessThan(Param, Param, Param),
+    /// Writes 1 if the parameters are equal, and 0 otherwise.
+    Equals(Param, Param, Param),
+    /// Adds the parameter to the relative base.
+    AdjustBase(Param),
+    Halt(),
+    /// An opcode from outside the standard set, run by `InstructionSet::execute_custom`.
+    Custom { opcode: Word, width: usize },
+}
+
+impl Instruction {
+    /// The number of memory words this instruction occupies, including the opcode.
+    pub fn width(&self) -> usize {
+        match self {
+            Instruction::Add(..) | Instruction::Mult(..) => 4,
+            Instruction::LessThan(..) | Instruction::Equals(..) => 4,
+            Instruction::JumpIfTrue(..) | Instruction::JumpIfFalse(..) => 3,
+            Instruction::Input(..) | Instruction::Output(..) | Instruction::AdjustBase(..) => 2,
+            Instruction::Halt() => 1,
+            Instruction::Custom { width, .. } => *width,
+        }
+    }
+
+    /// A rough cost for running this instruction: one cycle for each memory word
+    /// it reads or writes beyond its own encoding, and one more to run it.
+    pub fn cycles(&self) -> u64 {
+        let accesses = |params: &[Param]| params.iter()
+            .filter(|param| !matches!(param, Param::Immediate(_)))
+            .count() as u64;
+        1 + match self {
+            Instruction::Add(param_0, param_1, res) | Instruction::Mult(param_0, param_1, res)
+            | Instruction::LessThan(param_0, param_1, res) | Instruction::Equals(param_0, param_1, res) =>
+                accesses(&[*param_0, *param_1, *res]),
+            Instruction::JumpIfTrue(param_0, param_1) | Instruction::JumpIfFalse(param_0, param_1) =>
+                accesses(&[*param_0, *param_1]),
+            Instruction::Input(param) | Instruction::Output(param) | Instruction::AdjustBase(param) =>
+                accesses(&[*param]),
+            Instruction::Halt() | Instruction::Custom { .. } => 0,
+        }
+    }
+
+    /// The opcode this instruction was decoded from.
+    pub fn opcode(&self) -> Word {
+        match self {
+            Instruction::Add(..) => 1,
+            Instruction::Mult(..) => 2,
+            Instruction::Input(..) => 3,
+            Instruction::Output(..) => 4,
+            Instruction::JumpIfTrue(..) => 5,
+            Instruction::JumpIfFalse(..) => 6,
+            Instruction::LessThan(..) => 7,
+            Instruction::Equals(..) => 8,
+            Instruction::AdjustBase(..) => 9,
+            Instruction::Halt() => 99,
+            Instruction::Custom { opcode, .. } => *opcode,
+        }
+    }
+
+    /// The parameter this instruction writes to, if it writes to a known one.
+    fn write_target(&self) -> Option<Param> {
+        match self {
+            Instruction::Add(_, _, res) | Instruction::Mult(_, _, res) | Instruction::Input(res) => Some(*res),
+            Instruction::LessThan(_, _, res) | Instruction::Equals(_, _, res) => Some(*res),
+            Instruction::Output(..) | Instruction::AdjustBase(..) | Instruction::Halt() | Instruction::Custom { .. } => None,
+            Instruction::JumpIfTrue(..) | Instruction::JumpIfFalse(..) => None,
+        }
+    }
+
+    /// The parameters this instruction was decoded with, in order.
+    fn params(&self) -> Vec<Param> {
+        match *self {
+            Instruction::Add(param_0, param_1, res) | Instruction::Mult(param_0, param_1, res)
+            | Instruction::LessThan(param_0, param_1, res) | Instruction::Equals(param_0, param_1, res) =>
+                vec![param_0, param_1, res],
+            Instruction::JumpIfTrue(param_0, param_1) | Instruction::JumpIfFalse(param_0, param_1) => vec![param_0, param_1],
+            Instruction::Input(param) | Instruction::Output(param) | Instruction::AdjustBase(param) => vec![param],
+            Instruction::Halt() | Instruction::Custom { .. } => Vec::new(),
+        }
+    }
+
+    /// Where execution may go after running this instruction from `address`.
+    ///
+    /// Only immediate jump targets are known without running the program, so a jump
+    /// through any other parameter is only followed when it isn't taken.
+    fn successors(&self, address: Address) -> Vec<Address> {
+        let next = address + self.width();
+        let (condition, target, jumps_if_true) = match *self {
+            Instruction::Halt() => return Vec::new(),
+            Instruction::JumpIfTrue(condition, target) => (condition, target, true),
+            Instruction::JumpIfFalse(condition, target) => (condition, target, false),
+            _ => return vec![next],
+        };
+
+        let target = match target {
+            Param::Immediate(value) => Address::try_from(value).ok(),
+            Param::Position(_) | Param::Relative(_) => None,
+        };
+        match condition {
+            Param::Immediate(value) if (value.0 != 0) == jumps_if_true => target.into_iter().collect(),
+            Param::Immediate(_) => vec![next],
+            Param::Position(_) | Param::Relative(_) => target.into_iter().chain(Some(next)).collect(),
+        }
+    }
+}
+
+/// Decides what the opcodes in a program mean.
+///
+/// The default methods are the standard Intcode set. To add opcodes, decode them
+/// as `Instruction::Custom` and hand everything else to `StandardInstructions`.
+/// `Cpu::read`, `Cpu::write`, and `Cpu::address_at` give bounds checked access to memory,
+/// and `Cpu::param_at`, `Cpu::write_param_at`, and `Cpu::read_param` handle parameter modes.
+pub trait InstructionSet {
+    /// Decodes the instruction starting at `address`.
+    ///
+    /// The last two digits of the opcode pick the instruction, and the digits above
+    /// them give the mode of each parameter in turn.
+    fn decode(&self, address: Address, program: &dyn Memory) -> Result<Instruction, ExecError> {
+        let opcode = Word::from(Cpu::read(program, address)?);
+        let param = |index| Cpu::param_at(address, index, opcode, program);
+        let target = |index| Cpu::write_param_at(address, index, opcode, program);
+        let instruction = match opcode % 100 {
+            1 => {
+                let param_0 = param(0)?;
+                let param_1 = param(1)?;
+                let result_addr = target(2)?;
+                Instruction::Add(param_0, param_1, result_addr)
+            },
+            2 => {
+                let param_0 = param(0)?;
+                let param_1 = param(1)?;
+                let result_addr = target(2)?;
+                Instruction::Mult(param_0, param_1, result_addr)
+            },
+            3 => Instruction::Input(target(0)?),
+            4 => Instruction::Output(param(0)?),
+            5 => Instruction::JumpIfTrue(param(0)?, param(1)?),
+            6 => Instruction::JumpIfFalse(param(0)?, param(1)?),
+            7 => {
+                let param_0 = param(0)?;
+                let param_1 = param(1)?;
+                let result_addr = target(2)?;
+                Instruction::LessThan(param_0, param_1, result_addr)
+            },
+            8 => {
+                let param_0 = param(0)?;
+                let param_1 = param(1)?;
+                let result_addr = target(2)?;
+                Instruction::Equals(param_0, param_1, result_addr)
+            },
+            9 => Instruction::AdjustBase(param(0)?),
+            99 => Instruction::Halt(),
+            _ => return Err(ExecError::UnknownOpcode { opcode, address }),
+        };
+        Ok(instruction)
+    }
+
+    /// Runs an `Instruction::Custom` decoded at `address`.
+    fn execute_custom(&self, opcode: Word, address: Address, _memory: &mut dyn Memory) -> Result<(), ExecError> {
+        Err(ExecError::UnknownOpcode { opcode, address })
+    }
+}
+
+/// The opcodes every Intcode program uses.
+pub struct StandardInstructions;
+
+impl InstructionSet for StandardInstructions {}
+
+/// Where a `Cpu` goes after running one instruction.
+enum Flow {
+    /// On to the instruction just after it.
+    Next,
+    Jump(Address),
+    /// Nowhere; the run is over.
+    Stop(RunResult),
+}
+
+/// Why a run stopped without an error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HaltReason {
+    /// The program reached a `Halt` instruction.
+    Halted,
+    /// The run was cut off before the program halted.
+    StepLimitReached,
+    /// An `Input` instruction ran out of input under `InputPolicy::Halt`.
+    InputEnded,
+}
+
+/// What one `Cpu::step` did.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepResult {
+    /// An instruction ran, and there are more to come.
+    Continue,
+    /// The next instruction is an `Input` with no input queued; it runs once some is pushed.
+    NeedInput,
+    /// An `Output` instruction emitted this value.
+    Output(Word),
+    /// The program is at a `Halt`, and stepping again stays there.
+    Halted,
+}
+
+/// How a run ended, and where.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    pub reason: HaltReason,
+    /// The address of the `Halt` instruction, or of the next instruction that would have run.
+    pub instruction_ptr: Address,
+}
+
+impl RunResult {
+    fn new(reason: HaltReason, instruction_ptr: Address) -> Self {
+        RunResult {
+            reason,
+            instruction_ptr,
+        }
+    }
+}
+
+/// Somewhere for a program's `Output` instructions to send their values.
+pub trait OutputSink {
+    fn emit(&mut self, value: Value) -> Result<(), ExecError>;
+}
+
+/// Collects every output, oldest first.
+impl OutputSink for Vec<Value> {
+    fn emit(&mut self, value: Value) -> Result<(), ExecError> {
+        self.push(value);
+        Ok(())
+    }
+}
+
+/// Prints each output on its own line.
+impl OutputSink for io::Stdout {
+    fn emit(&mut self, value: Value) -> Result<(), ExecError> {
+        writeln!(self, "{}", value).map_err(|err| ExecError::Io(err.kind()))
+    }
+}
+
+/// Writes each output on its own line of the wrapped writer, in the given radix.
+pub struct LineSink<W: Write>(pub W, pub Radix);
+
+impl<W: Write> OutputSink for LineSink<W> {
+    fn emit(&mut self, value: Value) -> Result<(), ExecError> {
+        writeln!(self.0, "{}", self.1.format(value.0)).map_err(|err| ExecError::Io(err.kind()))
+    }
+}
+
+/// Sends each output to the wrapped closure.
+struct FnSink<O: FnMut(Word)>(O);
+
+impl<O: FnMut(Word)> OutputSink for FnSink<O> {
+    fn emit(&mut self, value: Value) -> Result<(), ExecError> {
+        (self.0)(value.0);
+        Ok(())
+    }
+}
+
+/// Something the CPU reports to a `Tracer` as it runs, beyond the instructions themselves.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The `Add`, `Mult`, `LessThan` or `Equals` at `address` computed `result` from
+    /// `lhs` and `rhs`, and is about to write it to `target`. Comparisons give 1 or 0.
+    Arithmetic { address: Address, opcode: Word, lhs: Value, rhs: Value, result: Value, target: Address },
+    /// An instruction is about to be fetched from, read or write `address` for the first
+    /// time, and it lies past the end of memory as it was when the `Cpu` started on it.
+    FirstTouch { address: Address },
+}
+
+/// Watches a `Cpu` run. Both methods do nothing unless overridden.
+pub trait Tracer {
+    /// Called with each instruction just before it runs, and memory as it is then.
+    fn on_instruction(&mut self, _ptr: Address, _instruction: &Instruction, _memory: &dyn Memory) {}
+
+    /// Called with each `TraceEvent` as it happens.
+    fn trace(&mut self, _event: TraceEvent) {}
+}
+
+/// Collects every event, oldest first.
+impl Tracer for Vec<TraceEvent> {
+    fn trace(&mut self, event: TraceEvent) {
+        self.push(event);
+    }
+}
+
+/// Prints each instruction to stderr as it runs, along with the values it reads.
+///
+/// Values the tracer can't see are shown as `?`: those at relative parameters, since
+/// it doesn't know the relative base, and the value an `Input` is about to read.
+pub struct StderrTracer;
+
+impl StderrTracer {
+    fn describe(ptr: Address, instruction: &Instruction, memory: &dyn Memory) -> String {
+        let value = |param: Param| match param {
+            Param::Immediate(value) => Some(value),
+            Param::Position(address) => Cpu::read(memory, address).ok(),
+            Param::Relative(_) => None,
+        };
+        let show = |param: Param| value(param).map_or_else(|| "?".to_string(), |value| value.to_string());
+
+        match *instruction {
+            Instruction::Add(param_0, param_1, res) => format!("exec ADD  @{:3}: {:3} {:3} ->{:3} ({:>3}+{:>3})",
+                ptr, param_0, param_1, res, show(param_0), show(param_1)),
+            Instruction::Mult(param_0, param_1, res) => format!("exec MULT @{:3}: {:3} {:3} ->{:3} ({:>3}*{:>3})",
+                ptr, param_0, param_1, res, show(param_0), show(param_1)),
+            Instruction::Input(res) => format!("exec IN   @{:3}: ->{:3} (  ?)", ptr, res),
+            Instruction::Output(param_0) => format!("exec OUT  @{:3}: {:3} ({})",
+                ptr, param_0, value(param_0).map_or_else(|| "?".to_string(), |value| format_value(value.0))),
+            Instruction::JumpIfTrue(param_0, param_1) => format!("exec JT   @{:3}: {:3} {:3} ({:>3} ->@{:>3})",
+                ptr, param_0, param_1, show(param_0), show(param_1)),
+            Instruction::JumpIfFalse(param_0, param_1) => format!("exec JF   @{:3}: {:3} {:3} ({:>3} ->@{:>3})",
+                ptr, param_0, param_1, show(param_0), show(param_1)),
+            Instruction::LessThan(param_0, param_1, res) => format!("exec LT   @{:3}: {:3} {:3} ->{:3} ({:>3}<{:>3})",
+                ptr, param_0, param_1, res, show(param_0), show(param_1)),
+            Instruction::Equals(param_0, param_1, res) => format!("exec EQ   @{:3}: {:3} {:3} ->{:3} ({:>3}={:>3})",
+                ptr, param_0, param_1, res, show(param_0), show(param_1)),
+            Instruction::AdjustBase(param_0) => format!("exec ARB  @{:3}: {:3} ({:>3})", ptr, param_0, show(param_0)),
+            Instruction::Halt() => format!("exec HALT @{:3}:", ptr),
+            Instruction::Custom { opcode, .. } => format!("exec #{:<3} @{:3}:", opcode, ptr),
+        }
+    }
+}
+
+impl Tracer for StderrTracer {
+    fn on_instruction(&mut self, ptr: Address, instruction: &Instruction, memory: &dyn Memory) {
+        eprintln!("{}", Self::describe(ptr, instruction, memory));
+    }
+}
+
+/// Everything needed to pick a run back up where it left off.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CpuSnapshot {
+    instruction_ptr: Address,
+    relative_base: Word,
+    inputs: Vec<Value>,
+    memory: IntcodeProgram,
+}
+
+impl CpuSnapshot {
+    /// Marks the start of a serialized snapshot, followed by the format version.
+    /// Builds with wide words write a different version, since their words are longer.
+    #[cfg(not(feature = "wide"))]
+    const MAGIC: &'static [u8; 4] = b"ICS2";
+    #[cfg(feature = "wide")]
+    const MAGIC: &'static [u8; 4] = b"ICW2";
+
+    const WORD_BYTES: usize = std::mem::size_of::<Word>();
+
+    /// Serializes the snapshot as the magic bytes followed by little-endian words:
+    /// the instruction pointer, the relative base, the input count and inputs, then the
+    /// memory length and memory.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let words = 4 + self.inputs.len() + self.memory.len();
+        let mut bytes = Vec::with_capacity(Self::MAGIC.len() + Self::WORD_BYTES * words);
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&(self.instruction_ptr.0 as Word).to_le_bytes());
+        bytes.extend_from_slice(&self.relative_base.to_le_bytes());
+        bytes.extend_from_slice(&(self.inputs.len() as Word).to_le_bytes());
+        for input in &self.inputs {
+            bytes.extend_from_slice(&input.0.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.memory.len() as Word).to_le_bytes());
+        for word in &self.memory.raw_program {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads back a snapshot written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CpuSnapshot, ExecError> {
+        let rest = bytes.strip_prefix(&Self::MAGIC[..]).ok_or(ExecError::CorruptSnapshot)?;
+        if rest.len() % Self::WORD_BYTES != 0 {
+            return Err(ExecError::CorruptSnapshot);
+        }
+        let mut words = rest.chunks_exact(Self::WORD_BYTES)
+            .map(|chunk| Word::from_le_bytes(chunk.try_into().unwrap()));
+
+        let instruction_ptr = Address(Self::next_len(&mut words)?);
+        let relative_base = words.next().ok_or(ExecError::CorruptSnapshot)?;
+        let input_count = Self::next_len(&mut words)?;
+        let inputs = Self::next_words(&mut words, input_count)?
+            .into_iter()
+            .map(Value)
+            .collect();
+        let memory_len = Self::next_len(&mut words)?;
+        let memory = Self::next_words(&mut words, memory_len)?;
+        if words.next().is_some() {
+            return Err(ExecError::CorruptSnapshot);
+        }
+
+        Ok(CpuSnapshot {
+            instruction_ptr,
+            relative_base,
+            inputs,
+            memory: IntcodeProgram::from_vec(memory),
+        })
+    }
+
+    fn next_len(words: &mut dyn Iterator<Item = Word>) -> Result<usize, ExecError> {
+        let word = words.next().ok_or(ExecError::CorruptSnapshot)?;
+        usize::try_from(word).map_err(|_| ExecError::CorruptSnapshot)
+    }
+
+    fn next_words(words: &mut dyn Iterator<Item = Word>, count: usize) -> Result<Vec<Word>, ExecError> {
+        let taken: Vec<Word> = words.take(count).collect();
+        if taken.len() == count {
+            Ok(taken)
+        } else {
+            Err(ExecError::CorruptSnapshot)
+        }
+    }
+}
+
+pub struct Cpu {
+    instruction_ptr: Address,
+    /// Where relative parameters count their offsets from.
+    relative_base: Word,
+    /// Values waiting to be read by `Input` instructions, oldest first.
+    inputs: VecDeque<Value>,
+    /// Values written by `Output` instructions when no other sink is given, oldest first.
+    outputs: Vec<Value>,
+    /// How many values the program has output, whichever sink they went to.
+    output_count: usize,
+    /// How many instructions have run to completion, not counting the `Halt` a run stops at.
+    instruction_count: u64,
+    /// The cost of those instructions, as `Instruction::cycles` weighs them.
+    cycle_count: u64,
+    input_policy: InputPolicy,
+    instruction_set: Box<dyn InstructionSet>,
+    tracer: Option<Box<dyn Tracer>>,
+    /// How many instructions a run may execute, unless it is given its own limit.
+    step_limit: u64,
+    /// Whether arithmetic fails with `ExecError::Overflow` rather than wrapping.
+    checked_arithmetic: bool,
+    /// Which memory this `Cpu` is running against, by where it lives, and how long it
+    /// was when the `Cpu` started on it, so scratch memory past that can be told apart.
+    loaded: Option<(usize, usize)>,
+    /// Addresses past the loaded length that have already been reported as `TraceEvent::FirstTouch`.
+    touched: HashSet<Address>,
+}
+
+impl Cpu {
+    pub fn new() -> Cpu {
+        Cpu {
+            instruction_ptr: Address(0),
+            relative_base: 0,
+            inputs: VecDeque::new(),
+            outputs: Vec::new(),
+            output_count: 0,
+            instruction_count: 0,
+            cycle_count: 0,
+            input_policy: InputPolicy::Error,
+            instruction_set: Box::new(StandardInstructions),
+            tracer: None,
+            step_limit: u64::MAX,
+            checked_arithmetic: false,
+            loaded: None,
+            touched: HashSet::new(),
+        }
+    }
+
+    /// Starts configuring a `Cpu`, beginning from what `Cpu::new` gives.
+    pub fn builder() -> CpuBuilder {
+        CpuBuilder { cpu: Cpu::new() }
+    }
+
+    /// Decodes and runs opcodes with `instruction_set` instead of the standard set.
+    pub fn set_instruction_set(&mut self, instruction_set: Box<dyn InstructionSet>) {
+        self.instruction_set = instruction_set;
+    }
+
+    /// Shows `tracer` each instruction and `TraceEvent` as the program runs, in place of any
+    /// tracer given before. `StderrTracer` prints each instruction as it goes.
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer>) {
+        self.tracer = Some(tracer);
+    }
+
+    /// Chooses what happens when the program asks for input and none is left.
+    pub fn set_input_policy(&mut self, input_policy: InputPolicy) {
+        self.input_policy = input_policy;
+    }
+
+    /// Creates a `Cpu` that will feed `inputs` to the program in order.
+    pub fn with_inputs(inputs: &[Word]) -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.inputs.extend(inputs.iter().map(|input| Value(*input)));
+        cpu
+    }
+
+    /// Queues `input` behind any the program hasn't read yet.
+    pub fn push_input(&mut self, input: Word) {
+        self.inputs.push_back(Value(input));
+    }
+
+    /// Whether the next `Input` instruction would read a queued value.
+    ///
+    /// An `Input` instruction only takes a value once it has run successfully,
+    /// so this stays true after a run that fails on that instruction.
+    pub fn has_input(&self) -> bool {
+        !self.inputs.is_empty()
+    }
+
+    /// The values output by the program so far.
+    pub fn outputs(&self) -> &[Value] {
+        &self.outputs
+    }
+
+    /// How many values the program has output so far, including any sent to another sink.
+    ///
+    /// Comparing this between runs tells whether new output is available without rescanning `outputs`.
+    pub fn output_count(&self) -> usize {
+        self.output_count
+    }
+
+    /// How many instructions this `Cpu` has run. Stopping at a `Halt` or for
+    /// want of input doesn't count.
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// A rough measure of the work this `Cpu` has done, adding up `Instruction::cycles`
+    /// for each instruction counted by `instruction_count`.
+    pub fn cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    /// Where the next instruction will be read from.
+    pub fn instruction_ptr(&self) -> Address {
+        self.instruction_ptr
+    }
+
+    /// Where relative parameters count their offsets from, which starts at 0.
+    pub fn relative_base(&self) -> Word {
+        self.relative_base
+    }
+
+    /// Captures the instruction pointer, pending input, and a copy of `memory`.
+    pub fn snapshot(&self, memory: &IntcodeProgram) -> CpuSnapshot {
+        CpuSnapshot {
+            instruction_ptr: self.instruction_ptr,
+            relative_base: self.relative_base,
+            inputs: self.inputs.iter().copied().collect(),
+            memory: memory.clone(),
+        }
+    }
+
+    /// Puts this `Cpu` back in the state `snapshot` captured, returning the memory to run against.
+    ///
+    /// Outputs collected so far are discarded, since the snapshot doesn't record them,
+    /// and so is the record of which addresses have been touched.
+    pub fn restore(&mut self, snapshot: &CpuSnapshot) -> IntcodeProgram {
+        self.instruction_ptr = snapshot.instruction_ptr;
+        self.relative_base = snapshot.relative_base;
+        self.inputs = snapshot.inputs.iter().copied().collect();
+        self.outputs.clear();
+        self.output_count = 0;
+        self.loaded = None;
+        self.touched.clear();
+        snapshot.memory.clone()
+    }
+
+    /// Runs until the program halts or the `Cpu`'s step limit is reached.
+    pub fn execute(&mut self, memory: &mut dyn Memory) -> Result<RunResult, ExecError> {
+        self.execute_with_limit(memory, self.step_limit)
+    }
+
+    /// Runs until the program halts or `max_steps` instructions have executed.
+    pub fn execute_with_limit(&mut self, memory: &mut dyn Memory, max_steps: u64) -> Result<RunResult, ExecError> {
+        let mut outputs = std::mem::take(&mut self.outputs);
+        let result = self.run(memory, max_steps, &mut 0, self.input_policy, &mut outputs);
+        self.outputs = outputs;
+        result
+    }
+
+    /// Runs like `execute_with_limit`, but treats running `max_steps` instructions
+    /// without halting as an `ExecError::StepLimitExceeded`.
+    ///
+    /// Useful for programs that must halt, where a runaway loop is a bug.
+    pub fn execute_within(&mut self, memory: &mut dyn Memory, max_steps: u64) -> Result<RunResult, ExecError> {
+        match self.execute_with_limit(memory, max_steps)? {
+            RunResult { reason: HaltReason::StepLimitReached, .. } =>
+                Err(ExecError::StepLimitExceeded { steps: max_steps }),
+            result => Ok(result),
+        }
+    }
+
+    /// Runs until the program halts, sending its outputs to `sink` instead of `outputs`.
+    pub fn execute_with_sink(&mut self, memory: &mut dyn Memory, sink: &mut dyn OutputSink) -> Result<RunResult, ExecError> {
+        self.run(memory, self.step_limit, &mut 0, self.input_policy, sink)
+    }
+
+    /// Queues `inputs`, then runs until the program halts or wants input it doesn't have,
+    /// returning the outputs from this call and which of the two happened.
+    ///
+    /// Waiting for input is reported as `HaltReason::InputEnded`, and calling again
+    /// with more inputs picks up at that `Input` instruction.
+    pub fn run_to_input_or_halt(&mut self, memory: &mut dyn Memory, inputs: &[Word])
+        -> Result<(Vec<Word>, HaltReason), ExecError>
+    {
+        self.inputs.extend(inputs.iter().map(|input| Value(*input)));
+        let mut outputs = Vec::new();
+        let result = self.run(memory, self.step_limit, &mut 0, InputPolicy::Halt, &mut outputs)?;
+
+        Ok((outputs.into_iter().map(Word::from).collect(), result.reason))
+    }
+
+    /// Runs with one integer per line of `input` as the program's input, writing each output as a line.
+    ///
+    /// Lines are only read when the program asks for input. Once `input` reaches
+    /// EOF, the `InputPolicy` decides what further `Input` instructions do.
+    pub fn run_interactive<R: BufRead, W: Write>(&mut self, memory: &mut dyn Memory, mut input: R, output: W)
+        -> Result<RunResult, ExecError>
+    {
+        let mut sink = LineSink(output, Radix::Decimal);
+        let mut at_eof = false;
+        // The step limit covers the whole call, not each stretch between inputs
+        let mut steps = 0;
+        loop {
+            let input_policy = if at_eof { self.input_policy } else { InputPolicy::Error };
+            let result = self.run(memory, self.step_limit, &mut steps, input_policy, &mut sink);
+
+            match result {
+                Err(ExecError::InputExhausted) if !at_eof => {
+                    let mut line = String::new();
+                    if input.read_line(&mut line).map_err(|err| ExecError::Io(err.kind()))? == 0 {
+                        at_eof = true;
+                    } else if !line.trim().is_empty() {
+                        let value = line.trim().parse()
+                            .map_err(|_| ExecError::InvalidInput(line.trim().to_string()))?;
+                        self.inputs.push_back(Value(value));
+                    }
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Runs until the program halts, calling `input` whenever it needs a value that
+    /// isn't already queued and `output` with each value it emits.
+    pub fn execute_with_io<I: FnMut() -> Word, O: FnMut(Word)>(&mut self, memory: &mut dyn Memory, mut input: I, output: O)
+        -> Result<RunResult, ExecError>
+    {
+        let mut sink = FnSink(output);
+        let mut steps = 0;
+        loop {
+            match self.run(memory, self.step_limit, &mut steps, InputPolicy::Error, &mut sink) {
+                Err(ExecError::InputExhausted) => self.inputs.push_back(Value(input())),
+                result => return result,
+            }
+        }
+    }
+
+    /// Runs a single instruction, keeping every bit of state for the next call, so
+    /// several `Cpu`s can take turns on their own memories.
+    ///
+    /// Outputs are returned here rather than collected in `outputs`.
+    pub fn step(&mut self, memory: &mut dyn Memory) -> Result<StepResult, ExecError> {
+        let mut outputs = Vec::new();
+        let result = self.run(memory, 1, &mut 0, InputPolicy::Halt, &mut outputs)?;
+        Ok(match result.reason {
+            HaltReason::Halted => StepResult::Halted,
+            HaltReason::InputEnded => StepResult::NeedInput,
+            HaltReason::StepLimitReached => match outputs.pop() {
+                Some(output) => StepResult::Output(output.0),
+                None => StepResult::Continue,
+            },
+        })
+    }
+
+    /// Runs until the program stops or `steps` reaches `max_steps`, counting each
+    /// instruction that completes in `steps` so a caller resuming after an error can
+    /// carry the count on.
+    fn run(&mut self, memory: &mut dyn Memory, max_steps: u64, steps: &mut u64, input_policy: InputPolicy,
+        sink: &mut dyn OutputSink) -> Result<RunResult, ExecError>
+    {
+        if memory.is_empty() {
+            return Err(ExecError::EmptyProgram);
+        }
+
+        // Running against different memory starts the first touches over
+        let memory_id = memory as *const dyn Memory as *const () as usize;
+        let loaded_len = match self.loaded {
+            Some((id, len)) if id == memory_id => len,
+            _ => {
+                self.loaded = Some((memory_id, memory.len()));
+                self.touched.clear();
+                memory.len()
+            },
+        };
+        loop {
+            if *steps >= max_steps {
+                return Ok(RunResult::new(HaltReason::StepLimitReached, self.instruction_ptr));
+            }
+
+            let address = self.instruction_ptr;
+            let instruction = self.instruction_at(address, memory)?;
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.on_instruction(address, &instruction, memory);
+            }
+            self.trace_first_touches(address, &instruction, loaded_len, memory.capacity());
+
+            let flow = self.run_instruction(address, &instruction, memory, input_policy, sink)
+                .map_err(|err| err.in_instruction(address, instruction.opcode()))?;
+            self.instruction_ptr = match flow {
+                Flow::Next => address + instruction.width(),
+                Flow::Jump(target) => target,
+                Flow::Stop(result) => return Ok(result),
+            };
+            *steps += 1;
+            self.instruction_count += 1;
+            self.cycle_count += instruction.cycles();
+        }
+    }
+
+    /// Runs the already decoded `instruction` at `address`, returning where to go next.
+    fn run_instruction(&mut self, address: Address, instruction: &Instruction, memory: &mut dyn Memory,
+        input_policy: InputPolicy, sink: &mut dyn OutputSink) -> Result<Flow, ExecError>
+    {
+        match *instruction {
+            Instruction::Add(param_0, param_1, res) => {
+                let lhs = self.read_param(memory, param_0)?;
+                let rhs = self.read_param(memory, param_1)?;
+                let result = if self.checked_arithmetic {
+                    lhs.0.checked_add(rhs.0).map(Value).ok_or(ExecError::Overflow { address })?
+                } else {
+                    lhs + rhs
+                };
+                let res = self.write_address(address, res)?;
+                self.trace_arithmetic(address, 1, lhs, rhs, result, res);
+                Self::write(memory, result, res)?;
+            },
+            Instruction::Mult(param_0, param_1, res) => {
+                let lhs = self.read_param(memory, param_0)?;
+                let rhs = self.read_param(memory, param_1)?;
+                let result = if self.checked_arithmetic {
+                    lhs.0.checked_mul(rhs.0).map(Value).ok_or(ExecError::Overflow { address })?
+                } else {
+                    lhs * rhs
+                };
+                let res = self.write_address(address, res)?;
+                self.trace_arithmetic(address, 2, lhs, rhs, result, res);
+                Self::write(memory, result, res)?;
+            },
+            Instruction::Input(res) => {
+                let input = match self.inputs.front() {
+                    Some(input) => *input,
+                    None => match input_policy {
+                        InputPolicy::Error => return Err(ExecError::InputExhausted),
+                        InputPolicy::Halt => return Ok(Flow::Stop(RunResult::new(HaltReason::InputEnded, address))),
+                        InputPolicy::Default(value) => Value(value),
+                    },
+                };
+                Self::write(memory, input, self.write_address(address, res)?)?;
+                self.inputs.pop_front();
+            },
+            Instruction::Output(param_0) => {
+                let output = self.read_param(memory, param_0)?;
+                sink.emit(output)?;
+                self.output_count += 1;
+            },
+            Instruction::JumpIfTrue(param_0, param_1) | Instruction::JumpIfFalse(param_0, param_1) => {
+                let condition = self.read_param(memory, param_0)?;
+                let target = self.read_param(memory, param_1)?;
+                let jumps_if_true = matches!(instruction, Instruction::JumpIfTrue(..));
+                if (condition.0 != 0) == jumps_if_true {
+                    let source = self.param_address(param_1)?.unwrap_or(address + 2);
+                    let target = Address::try_from(target)
+                        .map_err(|_| ExecError::InvalidAddress { value: target.0, address: source })?;
+                    return Ok(Flow::Jump(target));
+                }
+            },
+            Instruction::LessThan(param_0, param_1, res) => {
+                let lhs = self.read_param(memory, param_0)?;
+                let rhs = self.read_param(memory, param_1)?;
+                let result = Value((lhs.0 < rhs.0) as Word);
+                let res = self.write_address(address, res)?;
+                self.trace_arithmetic(address, 7, lhs, rhs, result, res);
+                Self::write(memory, result, res)?;
+            },
+            Instruction::Equals(param_0, param_1, res) => {
+                let lhs = self.read_param(memory, param_0)?;
+                let rhs = self.read_param(memory, param_1)?;
+                let result = Value((lhs.0 == rhs.0) as Word);
+                let res = self.write_address(address, res)?;
+                self.trace_arithmetic(address, 8, lhs, rhs, result, res);
+                Self::write(memory, result, res)?;
+            },
+            Instruction::AdjustBase(param_0) => {
+                let adjustment = self.read_param(memory, param_0)?;
+                self.relative_base = self.relative_base.wrapping_add(adjustment.0);
+            },
+            Instruction::Halt() => {
+                return Ok(Flow::Stop(RunResult::new(HaltReason::Halted, address)));
+            },
+            Instruction::Custom { opcode, .. } => {
+                self.instruction_set.execute_custom(opcode, address, memory)?;
+            },
+        }
+        Ok(Flow::Next)
+    }
+
+    fn trace_arithmetic(&mut self, address: Address, opcode: Word, lhs: Value, rhs: Value, result: Value, target: Address) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace(TraceEvent::Arithmetic { address, opcode, lhs, rhs, result, target });
+        }
+    }
+
+    /// Reports each address past `loaded_len` that no instruction has touched before,
+    /// out of those `instruction` was fetched from at `address` and those it refers to.
+    /// Addresses past `capacity` will fail instead, so they don't count.
+    fn trace_first_touches(&mut self, address: Address, instruction: &Instruction, loaded_len: usize, capacity: usize) {
+        if self.tracer.is_none() {
+            return;
+        }
+
+        let fetched = (address.0..address.0 + instruction.width()).map(Address);
+        let referred: Vec<Address> = instruction.params()
+            .into_iter()
+            .filter_map(|param| self.param_address(param).ok().flatten())
+            .collect();
+        for address in fetched.chain(referred) {
+            if address.0 < loaded_len || address.0 >= capacity {
+                continue;
+            }
+            if self.touched.insert(address) {
+                if let Some(tracer) = self.tracer.as_mut() {
+                    tracer.trace(TraceEvent::FirstTouch { address });
+                }
+            }
+        }
+    }
+
+    fn instruction_at(&self, address: Address, program: &dyn Memory) -> Result<Instruction, ExecError> {
+        self.instruction_set.decode(address, program).map_err(|err| match Self::read(program, address) {
+            Ok(opcode) => err.in_instruction(address, opcode.0),
+            Err(_) => err,
+        })
+    }
+
+    /// Reads the word at `address` and interprets it as a pointer.
+    pub fn address_at(address: Address, program: &dyn Memory) -> Result<Address, ExecError> {
+        let value = Self::read(program, address)?;
+        Address::try_from(value).map_err(|_| ExecError::InvalidAddress { value: value.0, address })
+    }
+
+    /// Decodes parameter `index`, counting from 0, of the instruction with `opcode` at `address`.
+    ///
+    /// A mode other than position (0), immediate (1), or relative (2) makes the opcode unknown.
+    pub fn param_at(address: Address, index: usize, opcode: Word, program: &dyn Memory) -> Result<Param, ExecError> {
+        let param_address = address + 1 + index;
+        match opcode / (100 * (10 as Word).pow(index as u32)) % 10 {
+            0 => Ok(Param::Position(Self::address_at(param_address, program)?)),
+            1 => Ok(Param::Immediate(Self::read(program, param_address)?)),
+            2 => Ok(Param::Relative(Self::read(program, param_address)?.0)),
+            _ => Err(ExecError::UnknownOpcode { opcode, address }),
+        }
+    }
+
+    /// Decodes parameter `index` like `param_at`, for an instruction that writes to it,
+    /// so immediate mode is an error.
+    pub fn write_param_at(address: Address, index: usize, opcode: Word, program: &dyn Memory)
+        -> Result<Param, ExecError>
+    {
+        match Self::param_at(address, index, opcode, program)? {
+            Param::Immediate(_) => Err(ExecError::ImmediateWriteTarget { address }),
+            param => Ok(param),
+        }
+    }
+
+    /// The address `param` refers to with the current relative base, or `None` if it is immediate.
+    pub fn param_address(&self, param: Param) -> Result<Option<Address>, ExecError> {
+        match param {
+            Param::Position(address) => Ok(Some(address)),
+            Param::Immediate(_) => Ok(None),
+            Param::Relative(offset) => {
+                let invalid = ExecError::InvalidRelativeAddress { base: self.relative_base, offset };
+                let value = self.relative_base.checked_add(offset).ok_or_else(|| invalid.clone())?;
+                Address::try_from(Value(value)).map(Some).map_err(|_| invalid)
+            },
+        }
+    }
+
+    /// The value `param` stands for: the value itself in immediate mode, or what is
+    /// at the address it refers to otherwise.
+    pub fn read_param(&self, memory: &dyn Memory, param: Param) -> Result<Value, ExecError> {
+        match param {
+            Param::Immediate(value) => Ok(value),
+            Param::Position(address) => Self::read(memory, address),
+            Param::Relative(_) => match self.param_address(param)? {
+                Some(address) => Self::read(memory, address),
+                None => unreachable!("relative parameters always have an address"),
+            },
+        }
+    }
+
+    /// The address the instruction at `address` writes to through `param`.
+    fn write_address(&self, address: Address, param: Param) -> Result<Address, ExecError> {
+        self.param_address(param)?.ok_or(ExecError::ImmediateWriteTarget { address })
+    }
+
+    /// Reads from `memory`, failing rather than panicking past its end.
+    pub fn read(memory: &dyn Memory, address: Address) -> Result<Value, ExecError> {
+        if address.0 < memory.capacity() {
+            Ok(memory.read_at(address))
+        } else {
+            Err(ExecError::OutOfBounds { address })
+        }
+    }
+
+    /// Writes to `memory`, failing rather than panicking past its end.
+    pub fn write(memory: &mut dyn Memory, value: Value, address: Address) -> Result<(), ExecError> {
+        if address.0 < memory.capacity() {
+            memory.write_at(value, address);
+            Ok(())
+        } else {
+            Err(ExecError::OutOfBounds { address })
+        }
+    }
+}
+
+/// Configures a `Cpu` one option at a time, starting from `Cpu::builder`.
+pub struct CpuBuilder {
+    cpu: Cpu,
+}
+
+impl CpuBuilder {
+    /// Queues `inputs` for the program, after any given before.
+    pub fn inputs(mut self, inputs: &[Word]) -> Self {
+        self.cpu.inputs.extend(inputs.iter().map(|input| Value(*input)));
+        self
+    }
+
+    pub fn input_policy(mut self, input_policy: InputPolicy) -> Self {
+        self.cpu.input_policy = input_policy;
+        self
+    }
+
+    pub fn instruction_set(mut self, instruction_set: Box<dyn InstructionSet>) -> Self {
+        self.cpu.instruction_set = instruction_set;
+        self
+    }
+
+    pub fn tracer(mut self, tracer: Box<dyn Tracer>) -> Self {
+        self.cpu.tracer = Some(tracer);
+        self
+    }
+
+    /// Stops runs with `HaltReason::StepLimitReached` after `step_limit` instructions,
+    /// unless they are given a limit of their own.
+    pub fn step_limit(mut self, step_limit: u64) -> Self {
+        self.cpu.step_limit = step_limit;
+        self
+    }
+
+    /// Fails arithmetic that overflows a `Word` with `ExecError::Overflow` instead of wrapping.
+    pub fn checked_arithmetic(mut self, checked_arithmetic: bool) -> Self {
+        self.cpu.checked_arithmetic = checked_arithmetic;
+        self
+    }
+
+    pub fn build(self) -> Cpu {
+        self.cpu
+    }
+}
+
+/// Steps a `Cpu` through a program one instruction at a time, and can undo recent steps.
+pub struct Debugger {
+    cpu: Cpu,
+    memory: IntcodeProgram,
+    /// How to undo each of the most recent steps, oldest first.
+    history: VecDeque<UndoEntry>,
+    max_history: usize,
+}
+
+/// What one step changed, so it can be put back.
+struct UndoEntry {
+    instruction_ptr: Address,
+    relative_base: Word,
+    /// How long memory was, so growing it can be undone.
+    memory_len: usize,
+    /// The address the step wrote to, and what was there before.
+    overwritten: Option<(Address, Value)>,
+    /// All of memory from before the step, for custom instructions that may write anywhere.
+    replaced_memory: Option<IntcodeProgram>,
+    consumed_input: Option<Value>,
+    outputs_len: usize,
+}
+
+impl Debugger {
+    /// Debugs `memory` on `cpu`, remembering up to `max_history` steps to undo.
+    pub fn new(cpu: Cpu, memory: IntcodeProgram, max_history: usize) -> Self {
+        Debugger {
+            cpu,
+            memory,
+            history: VecDeque::with_capacity(max_history),
+            max_history,
+        }
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn memory(&self) -> &IntcodeProgram {
+        &self.memory
+    }
+
+    /// Executes the next instruction.
+    pub fn step(&mut self) -> Result<RunResult, ExecError> {
+        let instruction_ptr = self.cpu.instruction_ptr;
+        let instruction = self.cpu.instruction_at(instruction_ptr, &self.memory)?;
+        let written = instruction.write_target();
+        let replaced_memory = match instruction {
+            Instruction::Custom { .. } => Some(self.memory.clone()),
+            _ => None,
+        };
+        let consumed_input = match instruction {
+            Instruction::Input(..) => self.cpu.inputs.front().copied(),
+            _ => None,
+        };
+        let entry = UndoEntry {
+            instruction_ptr,
+            relative_base: self.cpu.relative_base,
+            memory_len: self.memory.len(),
+            overwritten: written
+                .and_then(|param| self.cpu.param_address(param).ok()?)
+                .and_then(|address| Some((address, Cpu::read(&self.memory, address).ok()?))),
+            replaced_memory,
+            consumed_input,
+            outputs_len: self.cpu.outputs.len(),
+        };
+
+        let result = self.cpu.execute_with_limit(&mut self.memory, 1)?;
+
+        if self.max_history > 0 {
+            if self.history.len() == self.max_history {
+                self.history.pop_front();
+            }
+            self.history.push_back(entry);
+        }
+        Ok(result)
+    }
+
+    /// Undoes the most recent step, returning false once no history is left.
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.history.pop_back() {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        self.cpu.instruction_ptr = entry.instruction_ptr;
+        self.cpu.relative_base = entry.relative_base;
+        if let Some((address, value)) = entry.overwritten {
+            self.memory.write_at(value, address);
+        }
+        self.memory.raw_program.truncate(entry.memory_len);
+        if let Some(memory) = entry.replaced_memory {
+            self.memory = memory;
+        }
+        if let Some(input) = entry.consumed_input {
+            self.cpu.inputs.push_front(input);
+        }
+        self.cpu.output_count -= self.cpu.outputs.len() - entry.outputs_len;
+        self.cpu.outputs.truncate(entry.outputs_len);
+        true
+    }
+}
+
+/// Talks to a program in lines of ASCII text, stepping it with `Cpu::step`.
+///
+/// Outputs that are ASCII codes are collected as text, and anything else, such as a
+/// final score, is kept as a number in `values`.
+pub struct AsciiIo {
+    cpu: Cpu,
+    memory: IntcodeProgram,
+    text: String,
+    values: Vec<Word>,
+}
+
+impl AsciiIo {
+    pub fn new(cpu: Cpu, memory: IntcodeProgram) -> Self {
+        AsciiIo {
+            cpu,
+            memory,
+            text: String::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Queues each byte of `line` as an input, followed by a newline.
+    pub fn feed_line(&mut self, line: &str) {
+        for byte in line.bytes().chain(Some(b'\n')) {
+            self.cpu.push_input(Word::from(byte));
+        }
+    }
+
+    /// Runs until the program wants input that hasn't been fed to it, or halts,
+    /// returning `StepResult::NeedInput` or `StepResult::Halted` to say which.
+    pub fn run(&mut self) -> Result<StepResult, ExecError> {
+        loop {
+            match self.cpu.step(&mut self.memory)? {
+                StepResult::Continue => (),
+                StepResult::Output(value) => match u8::try_from(value) {
+                    Ok(byte) if byte.is_ascii() => self.text.push(char::from(byte)),
+                    _ => self.values.push(value),
+                },
+                result => return Ok(result),
+            }
+        }
+    }
+
+    /// The text output so far.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Takes the text output so far, leaving none behind.
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// Outputs that weren't ASCII, oldest first.
+    pub fn values(&self) -> &[Word] {
+        &self.values
+    }
+}
+
+/// Runs one program over and over, each time on a fresh copy of its memory.
+pub struct ProgramRunner {
+    original: IntcodeProgram,
+    /// Memory as the last run left it.
+    memory: IntcodeProgram,
+}
+
+impl ProgramRunner {
+    pub fn new(program: IntcodeProgram) -> Self {
+        ProgramRunner {
+            memory: program.clone(),
+            original: program,
+        }
+    }
+
+    /// Runs a copy of the original program with `patches` applied, until it halts.
+    pub fn run(&mut self, patches: &[(Address, Word)]) -> Result<RunResult, ExecError> {
+        run_into(&self.original, patches, &mut self.memory.raw_program)
+    }
+
+    /// The program as it was given.
+    pub fn original(&self) -> &IntcodeProgram {
+        &self.original
+    }
+
+    /// Memory as the last run left it, or the original program before any run.
+    pub fn memory(&self) -> &IntcodeProgram {
+        &self.memory
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct IntcodeProgram {
+    raw_program: Vec<Word>,
+}
+
+/// Grows with zeros to fit whatever is written, up to `MAX_MEMORY` words.
+impl Memory for IntcodeProgram {
+    fn read_at(&self, address: Address) -> Value {
+        Value(self.raw_program.get(address.0).copied().unwrap_or(0))
+    }
+
+    fn write_at(&mut self, value: Value, address: Address) {
+        if address.0 >= self.len() {
+            assert!(address.0 < MAX_MEMORY, "Address {} is past the most memory a program grows to ({} words)",
+                address, MAX_MEMORY);
+            self.raw_program.resize(address.0 + 1, 0);
+        }
+        *self.at_mut(address) = value.0;
+    }
+
+    fn len(&self) -> usize {
+        self.raw_program.len()
+    }
+
+    fn capacity(&self) -> usize {
+        MAX_MEMORY
+    }
+}
+
+impl IntcodeProgram {
+    /// Parses a comma separated program, panicking if it isn't one. Use `try_from_reader`
+    /// or `parse` to get the error instead.
+    pub fn from(s: &str) -> IntcodeProgram {
+        IntcodeProgram::try_from_reader(s.as_bytes()).unwrap_or_else(|err| panic!("Failed to parse code: {}", err))
+    }
+
+    /// Reads a comma separated program, allowing whitespace around each word and
+    /// empty tokens at the end, such as from a trailing comma or newline.
+    ///
+    /// Gzipped programs are recognized by their magic bytes and decompressed first.
+    pub fn try_from_reader<R: Read>(mut reader: R) -> Result<IntcodeProgram, IntcodeParseError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(|err| IntcodeParseError::Io(err.kind()))?;
+        if gzip::is_gzip(&bytes) {
+            bytes = gzip::decompress(&bytes).map_err(IntcodeParseError::Gzip)?;
+        }
+        let text = String::from_utf8(bytes).map_err(|_| IntcodeParseError::Io(io::ErrorKind::InvalidData))?;
+
+        let raw_program = IntcodeProgram::tokens(&text)
+            .iter()
+            .enumerate()
+            .map(|(index, token)| token.parse()
+                .map_err(|_| IntcodeParseError::InvalidToken { index, text: token.to_string() }))
+            .collect::<Result<_, _>>()?;
+
+        Ok(IntcodeProgram{
+            raw_program,
+        })
+    }
+
+    pub fn from_vec(v: Vec<Word>) -> IntcodeProgram {
+        IntcodeProgram{
+            raw_program: v,
+        }
+    }
+
+    /// Splits a program into trimmed words, dropping empty ones at the end such as from
+    /// a trailing comma or newline.
+    fn tokens(text: &str) -> Vec<&str> {
+        let mut tokens: Vec<&str> = text.split(',').map(str::trim).collect();
+        while tokens.last() == Some(&"") {
+            tokens.pop();
+        }
+        tokens
+    }
+
+    /// Parses comma separated words written in `radix`, allowing the same whitespace
+    /// and trailing empty tokens as `try_from_reader`.
+    pub fn from_str_radix(s: &str, radix: Radix) -> Result<IntcodeProgram, ParseIntError> {
+        let program_vec = IntcodeProgram::tokens(s)
+            .iter()
+            .map(|code| radix.parse(code))
+            .collect::<Result<_, _>>()?;
+
+        Ok(IntcodeProgram{
+            raw_program: program_vec,
+        })
+    }
+
+    /// Writes memory as comma separated words in `radix`, the way `from_str_radix` reads it.
+    pub fn dump(&self, radix: Radix) -> String {
+        self.raw_program.iter()
+            .map(|word| radix.format(*word))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Every address where `other` holds a different word, as `(address, ours, theirs)`.
+    ///
+    /// Words past the end of the shorter program are not compared.
+    pub fn diff(&self, other: &IntcodeProgram) -> Vec<(Address, Word, Word)> {
+        self.raw_program.iter()
+            .zip(other.raw_program.iter())
+            .enumerate()
+            .filter(|(_, (ours, theirs))| ours != theirs)
+            .map(|(address, (ours, theirs))| (Address(address), *ours, *theirs))
+            .collect()
+    }
+
+    /// The word stored at `address`, reading zero past the end like `read_at`.
+    pub fn at(&self, address: Address) -> Word {
+        self.read_at(address).0
+    }
+
+    /// The word stored at `address`, for writing. Unlike `at`, `address` must be
+    /// less than `len()`, and anything past the end panics.
+    fn at_mut(&mut self, address: Address) -> &mut Word {
+        let len = self.len();
+        match self.raw_program.get_mut(address.0) {
+            Some(value) => value,
+            None => panic!("Address {} is past the end of memory ({} words)", address, len),
+        }
+    }
+}
+
+impl Clone for IntcodeProgram {
+    fn clone(&self) -> Self {
+        let mut clone = Self {
+            raw_program: Vec::with_capacity(self.raw_program.len()),
+        };
+        for value in self.raw_program.iter() {
+            clone.raw_program.push(*value);
+        }
+        clone
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for IntcodeProgram {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IntcodeProgram::from_str_radix(s, Radix::Decimal)
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Cpu::new()
+    }
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecError::EmptyProgram => write!(f, "program is empty"),
+            ExecError::InvalidProgram(err) => write!(f, "program is not valid intcode: {}", err),
+            ExecError::InputExhausted => write!(f, "program needs more input"),
+            ExecError::UnknownOpcode { opcode, address } =>
+                write!(f, "unknown opcode {} at address {}", opcode, address),
+            ExecError::InvalidAddress { value, address } =>
+                write!(f, "parameter at address {} is not a valid address: {}", address, value),
+            ExecError::OutOfBounds { address } => write!(f, "address {} is out of bounds", address),
+            ExecError::StepLimitExceeded { steps } => write!(f, "program did not halt within {} steps", steps),
+            ExecError::ImmediateWriteTarget { address } =>
+                write!(f, "instruction at address {} writes to an immediate parameter", address),
+            ExecError::InvalidRelativeAddress { base, offset } =>
+                write!(f, "relative base {} plus offset {} is not a valid address", base, offset),
+            ExecError::InvalidInput(line) => write!(f, "input is not an integer: {:?}", line),
+            ExecError::Io(kind) => write!(f, "I/O failed: {:?}", kind),
+            ExecError::CorruptSnapshot => write!(f, "snapshot data is corrupt"),
+            ExecError::Overflow { address } => write!(f, "arithmetic overflowed at address {}", address),
+            ExecError::AtInstruction { address, opcode, error } =>
+                write!(f, "{} (opcode {} at address {})", error, opcode, address),
+        }
+    }
+}
+
+impl Error for ExecError {}
+
+impl fmt::Display for IntcodeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeParseError::InvalidToken { index, text } => write!(f, "token {} is not an integer: {:?}", index, text),
+            IntcodeParseError::Io(kind) => write!(f, "reading the program failed: {:?}", kind),
+            IntcodeParseError::Gzip(err) => write!(f, "decompressing the program failed: {}", err),
+        }
+    }
+}
+
+impl Error for IntcodeParseError {}
+
+impl From<ParseIntError> for ExecError {
+    fn from(err: ParseIntError) -> Self {
+        ExecError::InvalidProgram(err)
+    }
+}
+
+impl From<usize> for Address {
+    fn from(address: usize) -> Self {
+        Address(address)
+    }
+}
+
+impl From<Address> for usize {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+/// Only non-negative values can be used as pointers into memory.
+impl TryFrom<Value> for Address {
+    type Error = TryFromIntError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        usize::try_from(value.0).map(Address)
+    }
+}
+
+impl ops::Add<usize> for Address {
+    type Output = Address;
+
+    fn add(self, offset: usize) -> Self::Output {
+        Address(self.0 + offset)
+    }
+}
+
+impl From<Word> for Value {
+    fn from(value: Word) -> Self {
+        Value(value)
+    }
+}
+
+impl From<Value> for Word {
+    fn from(value: Value) -> Self {
+        value.0
+    }
+}
+
+/// Arithmetic wraps on overflow, like a two's complement machine word.
+impl ops::Add for Value {
+    type Output = Value;
+
+    fn add(self, other: Value) -> Self::Output {
+        Value(self.0.wrapping_add(other.0))
+    }
+}
+
+/// Arithmetic wraps on overflow, like a two's complement machine word.
+impl ops::Mul for Value {
+    type Output = Value;
+
+    fn mul(self, other: Value) -> Self::Output {
+        Value(self.0.wrapping_mul(other.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_address_from_value() {
+        assert_eq!(Address::try_from(Value(7)), Ok(Address(7)));
+        assert_eq!(usize::from(Address::from(12)), 12);
+    }
+
+    #[test]
+    fn test_negative_value_is_not_an_address() {
+        assert!(Address::try_from(Value(-1)).is_err());
+    }
+
+    #[test]
+    fn test_value_round_trip() {
+        assert_eq!(Word::from(Value::from(-42)), -42);
+        assert_eq!(Value(6) * Value(7) + Value(1), Value(43));
+    }
+
+    #[test]
+    fn test_instruction_width() {
+        let param = Param::Position(Address(0));
+        assert_eq!(Instruction::Add(param, param, param).width(), 4);
+        assert_eq!(Instruction::Mult(param, param, param).width(), 4);
+        assert_eq!(Instruction::Input(param).width(), 2);
+        assert_eq!(Instruction::Output(param).width(), 2);
+        assert_eq!(Instruction::JumpIfTrue(param, param).width(), 3);
+        assert_eq!(Instruction::JumpIfFalse(param, param).width(), 3);
+        assert_eq!(Instruction::LessThan(param, param, param).width(), 4);
+        assert_eq!(Instruction::Equals(param, param, param).width(), 4);
+        assert_eq!(Instruction::AdjustBase(param).width(), 2);
+        assert_eq!(Instruction::Halt().width(), 1);
+    }
+
+    #[test]
+    fn test_at_in_bounds() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 2, 3]);
+        *program.at_mut(Address(2)) = 7;
+
+        assert_eq!(program.at(Address(0)), 1);
+        assert_eq!(program.at(Address(2)), 7);
+        assert_eq!(program.read_at(Address(2)), Value(7));
+    }
+
+    #[test]
+    fn test_at_past_bounds_reads_zero() {
+        let program = IntcodeProgram::from_vec(vec![1, 2, 3]);
+        assert_eq!(program.at(Address(3)), 0);
+        assert_eq!(program.at(Address(MAX_MEMORY)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end of memory")]
+    fn test_at_mut_just_past_bounds() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 2, 3]);
+        *program.at_mut(Address(3)) = 7;
+    }
+
+    #[test]
+    fn test_diff() {
+        let original = IntcodeProgram::from_vec(vec![1, 2, 3, 4]);
+        let changed = IntcodeProgram::from_vec(vec![1, 5, 3, 6]);
+
+        assert_eq!(original.diff(&changed), vec![(Address(1), 2, 5), (Address(3), 4, 6)]);
+        assert!(original.diff(&original.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_execute_sample_program() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+        assert!(Cpu::new().execute(&mut program).is_ok());
+
+        assert_eq!(program.read_at(Address(0)), Value(3500));
+    }
+
+    #[test]
+    fn test_execute_with_patches() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 99, 7, 8]);
+        program.apply_patches(&[(Address(1), 5), (Address(2), 6)]).unwrap();
+
+        assert!(Cpu::new().execute(&mut program).is_ok());
+        assert_eq!(program.read_at(Address(0)), Value(15));
+
+        // Patches past the most memory a program grows to fail instead of allocating it
+        let far = Address(1 << 40);
+        assert_eq!(program.apply_patches(&[(Address(1), 7), (far, 1)]), Err(ExecError::OutOfBounds { address: far }));
+        assert_eq!(program.read_at(Address(1)), Value(7));
+        assert_eq!(run_and_read(&program, &[(far, 1)], Address(0)), Err(ExecError::OutOfBounds { address: far }));
+
+        let mut scratch = Vec::new();
+        assert_eq!(run_into(&program, &[(far, 1)], &mut scratch), Err(ExecError::OutOfBounds { address: far }));
+        assert_eq!(scratch.len(), program.len());
+        assert!(ProgramRunner::new(program).run(&[(Address(MAX_MEMORY), 1)]).is_err());
+    }
+
+    #[test]
+    fn test_halt_reports_instruction_ptr() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 99]);
+        let result = Cpu::new().execute(&mut program);
+
+        assert_eq!(result, Ok(RunResult::new(HaltReason::Halted, Address(4))));
+    }
+
+    #[test]
+    fn test_step_limit_reports_instruction_ptr() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 2, 0, 0, 0, 99]);
+        let result = Cpu::new().execute_with_limit(&mut program, 1);
+
+        assert_eq!(result, Ok(RunResult::new(HaltReason::StepLimitReached, Address(4))));
+        assert_eq!(program.read_at(Address(0)), Value(2));
+    }
+
+    #[test]
+    fn test_step_limit_exceeded() {
+        let mut program = IntcodeProgram::from_vec(vec![1105, 1, 0, 99]);
+        let mut cpu = Cpu::new();
+        assert_eq!(cpu.execute_within(&mut program, 1000), Err(ExecError::StepLimitExceeded { steps: 1000 }));
+        assert_eq!(cpu.instruction_count(), 1000);
+
+        let mut program = IntcodeProgram::from_vec(vec![1105, 0, 0, 99]);
+        assert_eq!(Cpu::new().execute_within(&mut program, 2), Ok(RunResult::new(HaltReason::Halted, Address(3))));
+        let mut program = IntcodeProgram::from_vec(vec![1105, 0, 0, 99]);
+        assert_eq!(Cpu::new().execute_within(&mut program, 1), Err(ExecError::StepLimitExceeded { steps: 1 }));
+    }
+
+    #[test]
+    fn test_instruction_count() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+        let mut cpu = Cpu::new();
+        assert_eq!((cpu.instruction_count(), cpu.cycle_count()), (0, 0));
+        assert!(cpu.execute(&mut program).is_ok());
+        // An add and a multiply, each reading two words and writing one
+        assert_eq!(cpu.instruction_count(), 2);
+        assert_eq!(cpu.cycle_count(), 8);
+
+        // Halting again doesn't add anything
+        assert!(cpu.execute(&mut program).is_ok());
+        assert_eq!(cpu.instruction_count(), 2);
+
+        // Immediate parameters don't touch memory, and waiting for input isn't counted
+        let mut program = IntcodeProgram::from("1101,1,2,0,104,7,3,0,99");
+        let mut cpu = Cpu::new();
+        assert_eq!(cpu.run_to_input_or_halt(&mut program, &[]), Ok((vec![7], HaltReason::InputEnded)));
+        assert_eq!(cpu.instruction_count(), 2);
+        assert_eq!(cpu.cycle_count(), 3);
+    }
+
+    #[test]
+    fn test_output_count() {
+        let mut program = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 4, 2, 99]);
+        let mut cpu = Cpu::with_inputs(&[5]);
+
+        let mut counts = Vec::new();
+        while cpu.execute_with_limit(&mut program, 1).unwrap().reason == HaltReason::StepLimitReached {
+            counts.push(cpu.output_count());
+        }
+        assert_eq!(counts, vec![0, 1, 2]);
+
+        // Outputs sent elsewhere still count
+        let mut program = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 99]);
+        let mut cpu = Cpu::with_inputs(&[5]);
+        let mut sink = Vec::new();
+        assert!(cpu.execute_with_sink(&mut program, &mut sink).is_ok());
+        assert_eq!(cpu.output_count(), 1);
+        assert!(cpu.outputs().is_empty());
+    }
+
+    #[test]
+    fn test_trace_arithmetic() {
+        struct Shared(Rc<RefCell<Vec<TraceEvent>>>);
+        impl Tracer for Shared {
+            fn trace(&mut self, event: TraceEvent) {
+                self.0.borrow_mut().trace(event);
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut program = IntcodeProgram::from_vec(vec![1, 0, 5, 6, 99, 41, 0]);
+        let mut cpu = Cpu::new();
+        cpu.set_tracer(Box::new(Shared(Rc::clone(&events))));
+        assert!(cpu.execute(&mut program).is_ok());
+
+        assert_eq!(*events.borrow(), vec![TraceEvent::Arithmetic {
+            address: Address(0),
+            opcode: 1,
+            lhs: Value(1),
+            rhs: Value(41),
+            result: Value(42),
+            target: Address(6),
+        }]);
+
+        // Comparisons report the 1 or 0 they write
+        events.borrow_mut().clear();
+        let mut program = IntcodeProgram::from("1107,3,5,9,1108,4,5,10,99,-1,-1");
+        let mut cpu = Cpu::new();
+        cpu.set_tracer(Box::new(Shared(Rc::clone(&events))));
+        assert!(cpu.execute(&mut program).is_ok());
+        assert_eq!(*events.borrow(), vec![
+            TraceEvent::Arithmetic {
+                address: Address(0),
+                opcode: 7,
+                lhs: Value(3),
+                rhs: Value(5),
+                result: Value(1),
+                target: Address(9),
+            },
+            TraceEvent::Arithmetic {
+                address: Address(4),
+                opcode: 8,
+                lhs: Value(4),
+                rhs: Value(5),
+                result: Value(0),
+                target: Address(10),
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_trace_first_touch() {
+        struct Shared(Rc<RefCell<Vec<TraceEvent>>>);
+        impl Tracer for Shared {
+            fn trace(&mut self, event: TraceEvent) {
+                self.0.borrow_mut().trace(event);
+            }
+        }
+
+        // Writes 7 to address 1000 twice, then reads it back from there
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut program = IntcodeProgram::from("1101,3,4,1000,1101,3,4,1000,4,1000,99");
+        let mut cpu = Cpu::new();
+        cpu.set_tracer(Box::new(Shared(Rc::clone(&events))));
+        assert!(cpu.execute(&mut program).is_ok());
+
+        assert_eq!(cpu.outputs(), &[Value(7)]);
+        let touches: Vec<TraceEvent> = events.borrow().iter()
+            .copied()
+            .filter(|event| matches!(event, TraceEvent::FirstTouch { .. }))
+            .collect();
+        assert_eq!(touches, vec![TraceEvent::FirstTouch { address: Address(1000) }]);
+
+        // Writes an output and a halt past the end, then jumps there and runs them
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut program = IntcodeProgram::from("1101,0,104,50,1101,0,99,52,1105,1,50");
+        let mut cpu = Cpu::new();
+        cpu.set_tracer(Box::new(Shared(Rc::clone(&events))));
+        let snapshot = cpu.snapshot(&program);
+        assert!(cpu.execute(&mut program).is_ok());
+        assert_eq!(cpu.outputs(), &[Value(0)]);
+
+        // Starting over on restored memory reports the same touches again
+        let mut restored = cpu.restore(&snapshot);
+        assert!(cpu.execute(&mut restored).is_ok());
+        let touches: Vec<Address> = events.borrow().iter()
+            .filter_map(|event| match event {
+                TraceEvent::FirstTouch { address } => Some(*address),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(touches, [50, 52, 51, 50, 52, 51].iter().copied().map(Address).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_tracer_sees_each_instruction() {
+        struct VecTracer(Rc<RefCell<Vec<Word>>>);
+        impl Tracer for VecTracer {
+            fn on_instruction(&mut self, _ptr: Address, instruction: &Instruction, _memory: &dyn Memory) {
+                self.0.borrow_mut().push(instruction.opcode());
+            }
+        }
+
+        let opcodes = Rc::new(RefCell::new(Vec::new()));
+        let mut program = IntcodeProgram::from("3,9,8,9,10,9,4,9,99,-1,8");
+        let mut cpu = Cpu::builder().inputs(&[8]).tracer(Box::new(VecTracer(Rc::clone(&opcodes)))).build();
+        assert!(cpu.execute(&mut program).is_ok());
+        assert_eq!(*opcodes.borrow(), vec![3, 8, 4, 99]);
+
+        let program = IntcodeProgram::from("1001,4,-2,5,99,7");
+        let instruction = StandardInstructions.decode(Address(0), &program).unwrap();
+        assert_eq!(StderrTracer::describe(Address(0), &instruction, &program),
+            "exec ADD  @  0: &  4 # -2 ->&  5 ( 99+ -2)");
+        let instruction = Instruction::Output(Param::Relative(3));
+        assert_eq!(StderrTracer::describe(Address(12), &instruction, &program), "exec OUT  @ 12: ~  3 (?)");
+    }
+
+    #[test]
+    fn test_cpu_builder() {
+        // Outputs an input, then squares a huge number and outputs that
+        let program = IntcodeProgram::from_vec(vec![3, 12, 4, 12, 2, 13, 13, 14, 4, 14, 99, 0, 0, Word::MAX, 0]);
+
+        let mut cpu = Cpu::builder().inputs(&[5]).step_limit(2).build();
+        let mut memory = program.clone();
+        assert_eq!(cpu.execute(&mut memory), Ok(RunResult::new(HaltReason::StepLimitReached, Address(4))));
+        assert_eq!(cpu.outputs(), &[Value(5)]);
+
+        let mut cpu = Cpu::builder().inputs(&[5]).checked_arithmetic(true).build();
+        let mut memory = program.clone();
+        assert_eq!(cpu.execute(&mut memory), Err(ExecError::Overflow { address: Address(4) }));
+
+        // Arithmetic wraps by default, and the policy stands in for the missing input
+        let mut cpu = Cpu::builder().input_policy(InputPolicy::Default(7)).build();
+        let mut memory = program.clone();
+        assert!(cpu.execute(&mut memory).is_ok());
+        assert_eq!(cpu.outputs(), &[Value(7), Value(1)]);
+
+        // An explicit limit still wins over the configured one
+        let mut cpu = Cpu::builder().inputs(&[5]).step_limit(1).build();
+        let mut memory = program;
+        assert_eq!(cpu.execute_with_limit(&mut memory, 3).unwrap().instruction_ptr, Address(8));
+    }
+
+    #[test]
+    fn test_input_error_leaves_instruction_ptr() {
+        let mut program = IntcodeProgram::from_vec(vec![3, 0, 99]);
+        let mut cpu = Cpu::new();
+
+        assert_eq!(cpu.execute(&mut program), Err(ExecError::InputExhausted));
+        assert_eq!(cpu.instruction_ptr(), Address(0));
+    }
+
+    #[test]
+    fn test_input_policy() {
+        let program = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 3, 0, 4, 0, 99]);
+
+        let mut cpu = Cpu::with_inputs(&[5]);
+        cpu.set_input_policy(InputPolicy::Default(-1));
+        assert!(cpu.execute(&mut program.clone()).is_ok());
+        assert_eq!(cpu.outputs(), &[Value(5), Value(-1)]);
+
+        let mut cpu = Cpu::with_inputs(&[5]);
+        cpu.set_input_policy(InputPolicy::Halt);
+        assert_eq!(cpu.execute(&mut program.clone()), Ok(RunResult::new(HaltReason::InputEnded, Address(4))));
+    }
+
+    #[test]
+    fn test_interactive_eof_terminates() {
+        let program = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 3, 0, 4, 0, 99]);
+
+        let mut output = Vec::new();
+        let mut cpu = Cpu::new();
+        cpu.set_input_policy(InputPolicy::Default(-1));
+        let result = cpu.run_interactive(&mut program.clone(), Cursor::new("5\n"), &mut output);
+        assert_eq!(result, Ok(RunResult::new(HaltReason::Halted, Address(8))));
+        assert_eq!(String::from_utf8(output).unwrap(), "5\n-1\n");
+
+        let mut output = Vec::new();
+        let mut cpu = Cpu::new();
+        cpu.set_input_policy(InputPolicy::Halt);
+        let result = cpu.run_interactive(&mut program.clone(), Cursor::new("5\n"), &mut output);
+        assert_eq!(result, Ok(RunResult::new(HaltReason::InputEnded, Address(4))));
+        assert_eq!(String::from_utf8(output).unwrap(), "5\n");
+
+        let mut cpu = Cpu::new();
+        let result = cpu.run_interactive(&mut program.clone(), Cursor::new("5\n"), io::sink());
+        assert_eq!(result, Err(ExecError::InputExhausted));
+    }
+
+    #[test]
+    fn test_interactive_rejects_bad_input() {
+        let mut program = IntcodeProgram::from_vec(vec![3, 0, 99]);
+        let result = Cpu::new().run_interactive(&mut program, Cursor::new("five\n"), io::sink());
+
+        assert_eq!(result, Err(ExecError::InvalidInput("five".to_string())));
+    }
+
+    #[test]
+    fn test_output_sinks() {
+        struct CountingSink(usize);
+
+        impl OutputSink for CountingSink {
+            fn emit(&mut self, _value: Value) -> Result<(), ExecError> {
+                self.0 += 1;
+                Ok(())
+            }
+        }
+
+        let program = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 4, 0, 99]);
+
+        let mut collected = Vec::new();
+        let mut cpu = Cpu::with_inputs(&[7]);
+        assert!(cpu.execute_with_sink(&mut program.clone(), &mut collected).is_ok());
+        assert_eq!(collected, vec![Value(7), Value(7)]);
+        assert!(cpu.outputs().is_empty());
+
+        let mut counter = CountingSink(0);
+        let mut cpu = Cpu::with_inputs(&[7]);
+        assert!(cpu.execute_with_sink(&mut program.clone(), &mut counter).is_ok());
+        assert_eq!(counter.0, 2);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        // Echoes two inputs, then adds 5 and 6 into address 17 and outputs that
+        let mut program = IntcodeProgram::from_vec(vec![3, 17, 4, 17, 3, 17, 4, 17, 1, 18, 19, 17, 4, 17, 99, 0, 0, 0, 5, 6]);
+
+        let mut cpu = Cpu::with_inputs(&[7, 8]);
+        assert_eq!(cpu.execute_with_limit(&mut program, 2).map(|result| result.reason), Ok(HaltReason::StepLimitReached));
+        assert_eq!(cpu.outputs(), &[Value(7)]);
+
+        let bytes = cpu.snapshot(&program).to_bytes();
+        let snapshot = CpuSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(snapshot, cpu.snapshot(&program));
+        assert_eq!(snapshot.inputs, vec![Value(8)]);
+
+        let mut resumed = Cpu::new();
+        let mut memory = resumed.restore(&snapshot);
+        assert_eq!(resumed.execute(&mut memory), Ok(RunResult::new(HaltReason::Halted, Address(14))));
+        assert_eq!(resumed.outputs(), &[Value(8), Value(11)]);
+
+        assert_eq!(CpuSnapshot::from_bytes(&bytes[..bytes.len() - 1]), Err(ExecError::CorruptSnapshot));
+        assert_eq!(CpuSnapshot::from_bytes(b"ICS0"), Err(ExecError::CorruptSnapshot));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_relative_base() {
+        let quine = IntcodeProgram::from("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+        let mut cpu = Cpu::new();
+        let mut memory = quine.clone();
+        assert!(cpu.execute_with_limit(&mut memory, 7).is_ok());
+        let snapshot = cpu.snapshot(&memory);
+        let outputs_before = cpu.outputs().to_vec();
+        assert!(cpu.execute(&mut memory).is_ok());
+        let finished = (cpu.outputs().to_vec(), memory.clone());
+
+        let bytes = snapshot.to_bytes();
+        assert_eq!(CpuSnapshot::from_bytes(&bytes).map(|snapshot| snapshot.relative_base), Ok(2));
+        for snapshot in [snapshot.clone(), CpuSnapshot::from_bytes(&bytes).unwrap()].iter() {
+            let mut memory = cpu.restore(snapshot);
+            assert_eq!(cpu.relative_base(), 2);
+            assert!(cpu.execute(&mut memory).is_ok());
+            let outputs: Vec<Value> = outputs_before.iter().chain(cpu.outputs()).copied().collect();
+            assert_eq!((outputs, memory), finished);
+        }
+    }
+
+    #[test]
+    fn test_debugger_step_back() {
+        // Reads into 9, doubles it into 10, and outputs that
+        let program = IntcodeProgram::from_vec(vec![3, 9, 1, 9, 9, 10, 4, 10, 99, 0, 0]);
+        let mut debugger = Debugger::new(Cpu::with_inputs(&[21]), program.clone(), 2);
+
+        assert!(debugger.step().is_ok());
+        let after_one = (debugger.cpu().instruction_ptr(), debugger.cpu().outputs().to_vec(), debugger.memory().clone());
+        assert!(debugger.step().is_ok());
+        assert!(debugger.step().is_ok());
+        assert_eq!(debugger.cpu().instruction_ptr(), Address(8));
+        assert_eq!(debugger.cpu().outputs(), &[Value(42)]);
+        assert_eq!(debugger.cpu().output_count(), 1);
+
+        assert!(debugger.step_back());
+        assert_eq!(debugger.cpu().output_count(), 0);
+        assert!(debugger.step_back());
+        let state = (debugger.cpu().instruction_ptr(), debugger.cpu().outputs().to_vec(), debugger.memory().clone());
+        assert_eq!(state, after_one);
+        assert_eq!(debugger.memory().at(Address(10)), 0);
+
+        // Only two steps were kept
+        assert!(!debugger.step_back());
+
+        // Replaying gives the same result
+        assert!(debugger.step().is_ok());
+        assert!(debugger.step().is_ok());
+        assert_eq!(debugger.cpu().outputs(), &[Value(42)]);
+    }
+
+    #[test]
+    fn test_debugger_restores_input() {
+        let program = IntcodeProgram::from_vec(vec![3, 5, 4, 5, 99, 0]);
+        let mut debugger = Debugger::new(Cpu::with_inputs(&[7]), program.clone(), 8);
+
+        assert!(debugger.step().is_ok());
+        assert!(debugger.step_back());
+        assert_eq!(debugger.memory(), &program);
+
+        assert!(debugger.step().is_ok());
+        assert!(debugger.step().is_ok());
+        assert_eq!(debugger.cpu().outputs(), &[Value(7)]);
+    }
+
+    #[test]
+    fn test_custom_instruction_set() {
+        /// Adds opcode 50, which negates the word its parameter points at.
+        struct WithNegate;
+
+        impl InstructionSet for WithNegate {
+            fn decode(&self, address: Address, program: &dyn Memory) -> Result<Instruction, ExecError> {
+                match program.read_at(address) {
+                    Value(50) => Ok(Instruction::Custom { opcode: 50, width: 2 }),
+                    _ => StandardInstructions.decode(address, program),
+                }
+            }
+
+            fn execute_custom(&self, opcode: Word, address: Address, memory: &mut dyn Memory) -> Result<(), ExecError> {
+                match opcode {
+                    50 => {
+                        let target = Cpu::address_at(address + 1, memory)?;
+                        let value = Cpu::read(memory, target)?;
+                        Cpu::write(memory, Value(-value.0), target)
+                    },
+                    _ => Err(ExecError::UnknownOpcode { opcode, address }),
+                }
+            }
+        }
+
+        let program = IntcodeProgram::from_vec(vec![50, 7, 1, 7, 7, 8, 99, 21, 0]);
+
+        let mut cpu = Cpu::new();
+        cpu.set_instruction_set(Box::new(WithNegate));
+        let mut memory = program.clone();
+        assert_eq!(cpu.execute(&mut memory), Ok(RunResult::new(HaltReason::Halted, Address(6))));
+        assert_eq!(memory.at(Address(7)), -21);
+        assert_eq!(memory.at(Address(8)), -42);
+
+        // The standard set doesn't know it
+        let mut memory = program.clone();
+        assert_eq!(Cpu::new().execute(&mut memory),
+            Err(ExecError::UnknownOpcode { opcode: 50, address: Address(0) }));
+    }
+
+    #[test]
+    fn test_find_reachable_halt() {
+        let halts = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 4, 0, 99]);
+        assert_eq!(find_reachable_halt(&halts), Some(Address(6)));
+
+        // Runs off the end of memory
+        let no_halt = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(find_reachable_halt(&no_halt), None);
+
+        // The 99 is only ever read as a parameter, and the opcode after it is unknown
+        let hidden_halt = IntcodeProgram::from_vec(vec![1, 3, 3, 99, 42]);
+        assert_eq!(find_reachable_halt(&hidden_halt), None);
+
+        assert!(find_reachable_halt(&IntcodeProgram::from(include_str!("../../day_2/input"))).is_some());
+
+        // Only reachable through a jump that is always taken
+        let jumps_over = IntcodeProgram::from_vec(vec![1105, 1, 4, 0, 99]);
+        assert_eq!(find_reachable_halt(&jumps_over), Some(Address(4)));
+        let never_jumps = IntcodeProgram::from_vec(vec![1106, 1, 4, 0, 99]);
+        assert_eq!(find_reachable_halt(&never_jumps), None);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(disassemble(&IntcodeProgram::from_vec(vec![1002, 4, 3, 4, 33])),
+            vec!["0000: MULT @4 #3 -> @4", "0004: DATA 33"]);
+
+        let program = IntcodeProgram::from("3,9,1105,-1,7,42,0,1201,9,-2,9,99,109,19,204,-34,1");
+        assert_eq!(disassemble(&program), vec![
+            "0000: IN -> @9",
+            "0002: JT #-1 #7",
+            "0005: DATA 42",
+            "0006: DATA 0",
+            "0007: ADD ~9 #-2 -> @9",
+            "0011: HALT",
+            "0012: ARB #19",
+            "0014: OUT ~-34",
+            // Too short to be a whole instruction
+            "0016: DATA 1",
+        ]);
+    }
+
+    #[test]
+    fn test_output_mismatch() {
+        assert_same_outputs("3,0,4,0,99", &[7], &[7]);
+        assert_eq!(output_mismatch("3,0,4,0,99", &[7], &[7]), None);
+
+        let mismatch = output_mismatch("3,0,4,0,4,0,99", &[7], &[7, 8]).unwrap();
+        assert!(mismatch.starts_with("outputs differ first at index 1: got Some(7), expected Some(8)"));
+
+        let mismatch = output_mismatch("3,0,4,0,99", &[7], &[7, 7]).unwrap();
+        assert!(mismatch.starts_with("outputs differ first at index 1: got None, expected Some(7)"));
+
+        let mismatch = output_mismatch("3,0,99", &[], &[]).unwrap();
+        assert_eq!(mismatch, "program failed: program needs more input");
+    }
+
+    #[test]
+    #[should_panic(expected = "outputs differ first at index 0")]
+    fn test_assert_same_outputs_catches_mismatch() {
+        assert_same_outputs("3,0,4,0,99", &[7], &[8]);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 of 2 fixtures differ:\nfixture 1: outputs differ first at index 0")]
+    fn test_assert_all_same_outputs_catches_mismatch() {
+        assert_all_same_outputs(&[
+            ("3,0,4,0,99", &[7], &[7]),
+            ("3,0,4,0,99", &[7], &[-7]),
+        ]);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        assert_eq!(dry_run(&IntcodeProgram::from(include_str!("../../day_2/input"))), Ok(()));
+        assert_eq!(dry_run(&IntcodeProgram::from_vec(vec![3, 0, 4, 0, 99])), Ok(()));
+
+        // Writes past where memory can grow to
+        let program = IntcodeProgram::from_vec(vec![1, 0, 0, MAX_MEMORY as Word, 99]);
+        assert_eq!(dry_run(&program), Err(ExecError::OutOfBounds { address: Address(MAX_MEMORY) }));
+        assert_eq!(dry_run(&IntcodeProgram::from_vec(vec![1, 0, 0, 50, 99])), Ok(()));
+
+        let program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 42]);
+        assert_eq!(dry_run(&program), Err(ExecError::UnknownOpcode { opcode: 42, address: Address(4) }));
+
+        let program = IntcodeProgram::from_vec(vec![4, -1, 99]);
+        assert_eq!(dry_run(&program), Err(ExecError::InvalidAddress { value: -1, address: Address(1) }));
+
+        // Falls off the end of memory, where everything reads as 0
+        let program = IntcodeProgram::from_vec(vec![4, 0]);
+        assert_eq!(dry_run(&program), Err(ExecError::UnknownOpcode { opcode: 0, address: Address(2) }));
+    }
+
+    #[test]
+    fn test_program_runner() {
+        let mut runner = ProgramRunner::new(IntcodeProgram::from_vec(vec![1, 0, 0, 0, 99, 7, 8]));
+
+        assert_eq!(runner.run(&[(Address(1), 5), (Address(2), 6)]), Ok(RunResult::new(HaltReason::Halted, Address(4))));
+        assert_eq!(runner.memory().at(Address(0)), 15);
+
+        // Starts over from the original rather than the last run's memory
+        assert!(runner.run(&[(Address(1), 5), (Address(2), 5)]).is_ok());
+        assert_eq!(runner.memory().at(Address(0)), 14);
+        assert_eq!(runner.memory().at(Address(2)), 5);
+        assert_eq!(runner.original().at(Address(0)), 1);
+    }
+
+    #[test]
+    fn test_run_into() {
+        let program = IntcodeProgram::from(include_str!("../../day_2/input"));
+        let mut scratch = Vec::new();
+
+        for noun in 0..10 {
+            for verb in 0..10 {
+                let patches = [(Address(1), noun), (Address(2), verb)];
+                let mut fresh = program.clone();
+                fresh.apply_patches(&patches).unwrap();
+                let fresh_result = Cpu::new().execute(&mut fresh);
+
+                assert_eq!(run_into(&program, &patches, &mut scratch), fresh_result);
+                assert_eq!(IntcodeProgram::from_vec(scratch.clone()), fresh);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_to_input_or_halt() {
+        // Outputs 5 and 6, then reads a value and outputs it doubled
+        let mut program = IntcodeProgram::from_vec(vec![4, 13, 4, 14, 3, 15, 1, 15, 15, 15, 4, 15, 99, 5, 6, 0]);
+        let mut cpu = Cpu::new();
+
+        assert_eq!(cpu.run_to_input_or_halt(&mut program, &[]), Ok((vec![5, 6], HaltReason::InputEnded)));
+        assert_eq!(cpu.instruction_ptr(), Address(4));
+        assert_eq!(cpu.run_to_input_or_halt(&mut program, &[21]), Ok((vec![42], HaltReason::Halted)));
+        assert!(cpu.outputs().is_empty());
+    }
+
+    #[test]
+    fn test_has_input() {
+        let mut program = IntcodeProgram::from_vec(vec![3, 0, 3, 0, 99]);
+        let mut cpu = Cpu::new();
+        assert!(!cpu.has_input());
+
+        cpu.push_input(1);
+        cpu.push_input(2);
+        assert!(cpu.has_input());
+        assert!(cpu.execute_with_limit(&mut program, 1).is_ok());
+        assert!(cpu.has_input());
+        assert!(cpu.execute_with_limit(&mut program, 1).is_ok());
+        assert!(!cpu.has_input());
+        assert_eq!(program.at(Address(0)), 2);
+
+        // An input that can't be written isn't consumed
+        let mut program = IntcodeProgram::from_vec(vec![3, MAX_MEMORY as Word, 99]);
+        let mut cpu = Cpu::with_inputs(&[7]);
+        assert_eq!(cpu.execute(&mut program).map_err(|err| err.root_cause().clone()),
+            Err(ExecError::OutOfBounds { address: Address(MAX_MEMORY) }));
+        assert!(cpu.has_input());
+    }
+
+    #[test]
+    fn test_try_from_reader() {
+        let err = IntcodeProgram::try_from_reader("1,2,x,4".as_bytes()).unwrap_err();
+        assert_eq!(err, IntcodeParseError::InvalidToken { index: 2, text: "x".to_string() });
+        assert_eq!(err.to_string(), "token 2 is not an integer: \"x\"");
+
+        let program = IntcodeProgram::try_from_reader(" 1, 2,\n3 ,99,\n,\n".as_bytes());
+        assert_eq!(program, Ok(IntcodeProgram::from_vec(vec![1, 2, 3, 99])));
+        assert_eq!(IntcodeProgram::try_from_reader("".as_bytes()), Ok(IntcodeProgram::from_vec(vec![])));
+        // Only trailing tokens may be empty
+        assert_eq!(IntcodeProgram::try_from_reader("1,,99".as_bytes()),
+            Err(IntcodeParseError::InvalidToken { index: 1, text: String::new() }));
+    }
+
+    #[test]
+    fn test_try_from_gzip_reader() {
+        let plain = IntcodeProgram::from(include_str!("../../day_2/input"));
+        let gzipped = IntcodeProgram::try_from_reader(&include_bytes!("../../day_2/input.gz")[..]);
+        assert_eq!(gzipped, Ok(plain));
+
+        // The magic bytes alone aren't a gzip stream
+        assert!(matches!(IntcodeProgram::try_from_reader(&[0x1f, 0x8b, 0x08][..]), Err(IntcodeParseError::Gzip(_))));
+    }
+
+    #[test]
+    fn test_radix_dump() {
+        let program = IntcodeProgram::from_vec(vec![1, 0, 0, 255, -26, 99, 0]);
+
+        let mut echo = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 4, 7, 99, 255]);
+        let mut printed = Vec::new();
+        let mut cpu = Cpu::with_inputs(&[-26]);
+        assert!(cpu.execute_with_sink(&mut echo, &mut LineSink(&mut printed, Radix::Hex)).is_ok());
+        assert_eq!(String::from_utf8(printed).unwrap(), "-1a\nff\n");
+
+        let hex = program.dump(Radix::Hex);
+        assert_eq!(hex, "1,0,0,ff,-1a,63,0");
+        assert_eq!(IntcodeProgram::from_str_radix(&hex, Radix::Hex), Ok(program.clone()));
+
+        let binary = program.dump(Radix::Binary);
+        assert_eq!(IntcodeProgram::from_str_radix(&binary, Radix::Binary), Ok(program.clone()));
+        assert_eq!(program.dump(Radix::Decimal), "1,0,0,255,-26,99,0");
+
+        assert_eq!(Radix::Octal.format(Word::MIN), format!("-{:o}", Word::MIN.unsigned_abs()));
+        assert_eq!(Radix::Octal.parse(&Radix::Octal.format(Word::MIN)), Ok(Word::MIN));
+        assert!(IntcodeProgram::from_str_radix("1,2,ff", Radix::Decimal).is_err());
+        assert_eq!(IntcodeProgram::from_str_radix(" 1, ff ,\n", Radix::Hex), Ok(IntcodeProgram::from_vec(vec![1, 255])));
+    }
+
+    #[test]
+    fn test_run_and_read() {
+        let program = IntcodeProgram::from(include_str!("../../day_2/input"));
+        let patches = [(Address(1), 12), (Address(2), 2)];
+
+        assert_eq!(run_and_read(&program, &patches, Address(0)), Ok(4090689));
+        assert_eq!(run_and_read(&program, &patches, Address(1)), Ok(12));
+        assert_eq!(run_and_read(&program, &patches, Address(10_000)), Ok(0));
+        assert!(matches!(run_and_read(&program, &patches, Address(MAX_MEMORY)), Err(ExecError::OutOfBounds { .. })));
+        // Failing inside the run rather than reading afterwards says which instruction failed
+        assert!(matches!(run_and_read(&program, &[(Address(1), MAX_MEMORY as Word)], Address(0)),
+            Err(ExecError::AtInstruction { address: Address(0), opcode: 1, .. })));
+    }
+
+    #[test]
+    #[cfg(not(feature = "wide"))]
+    fn test_format_value() {
+        assert_eq!(format_value(1125899906842624), "1125899906842624 (0x0004000000000000)");
+        assert_eq!(format_value(-1), "-1 (0xffffffffffffffff)");
+        assert_eq!(format_value(0), "0 (0x0000000000000000)");
+    }
+
+    #[test]
+    #[cfg(feature = "wide")]
+    fn test_wide_arithmetic() {
+        let big = i64::MAX as Word;
+        // Squares its input, then adds the input again
+        let outputs = run_program_str("3,13,2,13,13,14,1,14,13,14,4,14,99,0,0", &[big]).unwrap();
+        assert_eq!(outputs, vec![big * big + big]);
+        assert!(outputs[0] > i64::MAX as Word);
+
+        assert_eq!(format_value(-1), format!("-1 (0x{})", "f".repeat(32)));
+    }
+
+    #[test]
+    fn test_last_output() {
+        assert_eq!(last_output("3,0,4,0,99", &[7]), Ok(Some(7)));
+        assert_eq!(last_output("3,0,4,0,4,2,99", &[7]), Ok(Some(4)));
+        assert_eq!(last_output("1,0,0,0,99", &[]), Ok(None));
+        assert_eq!(last_output("3,0,4,0,99", &[]), Err(ExecError::InputExhausted));
+    }
+
+    #[test]
+    fn test_pipe() {
+        let echo = "3,0,4,0,99";
+        let echo_twice = "3,0,4,0,3,0,4,0,99";
+        let increment = "3,9,1,9,10,9,4,9,99,0,1";
+
+        assert_eq!(pipe(echo, &[41], increment), Ok(vec![42]));
+        assert_eq!(pipe(echo_twice, &[41, 7], echo_twice), Ok(vec![41, 7]));
+        // The consumer halts before reading the producer's second output
+        assert_eq!(pipe(echo_twice, &[41, 7], increment), Ok(vec![42]));
+        // The producer halts before giving the consumer anything
+        assert_eq!(pipe("1,0,0,0,99", &[], increment), Err(ExecError::InputExhausted));
+        assert_eq!(pipe(echo, &[], increment), Err(ExecError::InputExhausted));
+    }
+
+    #[test]
+    fn test_run_catching() {
+        assert_eq!(run_catching("3,0,4,0,99", &[7]), Ok(RunResult::new(HaltReason::Halted, Address(4))));
+
+        assert!(matches!(run_catching("1,0,x,0,99", &[]), Err(ExecError::InvalidProgram(_))));
+        assert_eq!(run_catching("", &[]), Err(ExecError::EmptyProgram));
+        // Running off the end finds the zeros past it
+        assert_eq!(run_catching("1,0,0", &[]), Err(ExecError::UnknownOpcode { opcode: 0, address: Address(4) }));
+        assert_eq!(run_catching("1,0,0,9,99", &[]), Ok(RunResult::new(HaltReason::Halted, Address(4))));
+        assert_eq!(run_catching("2,0,0,1048576,99", &[]).map_err(|err| err.root_cause().clone()),
+            Err(ExecError::OutOfBounds { address: Address(MAX_MEMORY) }));
+        assert_eq!(run_catching("3,0,99", &[]), Err(ExecError::InputExhausted));
+        assert_eq!(run_catching("4,-1,99", &[]).map_err(|err| err.root_cause().clone()),
+            Err(ExecError::InvalidAddress { value: -1, address: Address(1) }));
+    }
+
+    #[test]
+    fn test_run_program_str_echo() {
+        assert_eq!(run_program_str("3,0,4,0,99", &[42]), Ok(vec![42]));
+    }
+
+    #[test]
+    fn test_parse_like_try_from_reader() {
+        let expected = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 99]);
+        assert_eq!("1,0,0,0,99,".parse(), Ok(expected.clone()));
+        assert_eq!("1, 0,0 ,0,99\n".parse(), Ok(expected));
+        assert!("1,,0,0,99".parse::<IntcodeProgram>().is_err());
+
+        assert_eq!(run_program_str("3,0,4,0,99,\n", &[5]), Ok(vec![5]));
+        assert_eq!(last_output(" 3, 0, 4, 0, 99", &[6]), Ok(Some(6)));
+        assert!(run_catching("1,0,0,0,99,", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_run_program_str_errors() {
+        assert_eq!(run_program_str("3,0,4,0,99", &[]), Err(ExecError::InputExhausted));
+        assert!(matches!(run_program_str("3,x,99", &[]), Err(ExecError::InvalidProgram(_))));
+    }
+
+    #[test]
+    fn test_execute_empty_program() {
+        let mut program = IntcodeProgram::from_vec(vec![]);
+
+        assert_eq!(Cpu::new().execute(&mut program), Err(ExecError::EmptyProgram));
+    }
+
+    /// A small xorshift generator, so fuzz failures can be reproduced from the seed.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound
+        }
+    }
+
+    #[test]
+    fn test_fuzz_decoding_never_panics() {
+        const OPCODES: [Word; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+        let mut rng = XorShift(0x2019_1202);
+
+        for _ in 0..10_000 {
+            let len = 1 + rng.below(16) as usize;
+            let raw_program: Vec<Word> = (0..len)
+                .map(|_| match rng.below(3) {
+                    0 => match OPCODES[rng.below(OPCODES.len() as u64) as usize] {
+                        99 => 99,
+                        // Position, immediate, or relative mode for each of up to three parameters
+                        opcode => opcode + [100, 1_000, 10_000].iter().map(|place| rng.below(3) as Word * place).sum::<Word>(),
+                    },
+                    _ => rng.below(len as u64 + 6) as Word - 3,
+                })
+                .collect();
+
+            let mut program = IntcodeProgram::from_vec(raw_program.clone());
+            let mut cpu = Cpu::with_inputs(&[1, 2]);
+            match cpu.execute_with_limit(&mut program, 1_000) {
+                Ok(RunResult { reason: HaltReason::Halted, instruction_ptr }) => {
+                    assert_eq!(program.read_at(instruction_ptr), Value(99), "{:?}", raw_program);
+                },
+                Ok(RunResult { reason: HaltReason::StepLimitReached, .. }) => (),
+                Ok(result) => panic!("Unexpected result {:?} for {:?}", result, raw_program),
+                Err(err) if matches!(err.root_cause(), ExecError::UnknownOpcode { .. }
+                    | ExecError::InvalidAddress { .. }
+                    | ExecError::OutOfBounds { .. }
+                    | ExecError::ImmediateWriteTarget { .. }
+                    | ExecError::InvalidRelativeAddress { .. }
+                    | ExecError::InputExhausted) => (),
+                Err(err) => panic!("Unexpected error {:?} for {:?}", err, raw_program),
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_with_io() {
+        let mut program = IntcodeProgram::from_vec(vec![3, 0, 4, 0, 99]);
+        let mut inputs = vec![42];
+        let mut outputs = Vec::new();
+        let result = Cpu::new().execute_with_io(&mut program, || inputs.pop().unwrap(), |value| outputs.push(value));
+
+        assert_eq!(result.map(|result| result.reason), Ok(HaltReason::Halted));
+        assert_eq!(outputs, vec![42]);
+        assert!(inputs.is_empty());
+
+        // Queued inputs are used up before asking for more
+        let mut program = IntcodeProgram::from("3,9,3,10,4,9,4,10,99,0,0");
+        let mut outputs = Vec::new();
+        let result = Cpu::with_inputs(&[1]).execute_with_io(&mut program, || 2, |value| outputs.push(value));
+        assert!(result.is_ok());
+        assert_eq!(outputs, vec![1, 2]);
+
+        // Asking for input doesn't start the step limit over
+        let program = IntcodeProgram::from("3,5,1105,1,0,0");
+        let mut cpu = Cpu::builder().step_limit(10).build();
+        let result = cpu.execute_with_io(&mut program.clone(), || 1, |_| ());
+        assert_eq!(result.map(|result| result.reason), Ok(HaltReason::StepLimitReached));
+        assert_eq!(cpu.instruction_count(), 10);
+
+        let mut cpu = Cpu::builder().step_limit(10).build();
+        let result = cpu.run_interactive(&mut program.clone(), Cursor::new("1\n".repeat(100)), io::sink());
+        assert_eq!(result.map(|result| result.reason), Ok(HaltReason::StepLimitReached));
+        assert_eq!(cpu.instruction_count(), 10);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        // Equal to 8, less than 8, in position and then immediate mode
+        let programs = [
+            ("3,9,8,9,10,9,4,9,99,-1,8", [0, 1, 0]),
+            ("3,9,7,9,10,9,4,9,99,-1,8", [1, 0, 0]),
+            ("3,3,1108,-1,8,3,4,3,99", [0, 1, 0]),
+            ("3,3,1107,-1,8,3,4,3,99", [1, 0, 0]),
+        ];
+        for (src, expected) in programs.iter() {
+            for (input, output) in [7, 8, 9].iter().zip(expected.iter()) {
+                assert_eq!(run_program_str(src, &[*input]), Ok(vec![*output]), "{} with {}", src, input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_jumps() {
+        // Outputs 0 if the input was 0 and 1 otherwise
+        for src in ["3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", "3,3,1105,-1,9,1101,0,0,12,4,12,99,1"].iter() {
+            assert_eq!(run_program_str(src, &[0]), Ok(vec![0]), "{}", src);
+            assert_eq!(run_program_str(src, &[5]), Ok(vec![1]), "{}", src);
+        }
+
+        let large = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+            1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+            999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        assert_eq!(run_program_str(large, &[7]), Ok(vec![999]));
+        assert_eq!(run_program_str(large, &[8]), Ok(vec![1000]));
+        assert_eq!(run_program_str(large, &[9]), Ok(vec![1001]));
+
+        let mut program = IntcodeProgram::from_vec(vec![1105, 1, -2, 99]);
+        assert_eq!(Cpu::new().execute(&mut program).map_err(|err| err.root_cause().clone()),
+            Err(ExecError::InvalidAddress { value: -2, address: Address(2) }));
+    }
+
+    #[test]
+    fn test_parameter_modes() {
+        let mut program = IntcodeProgram::from_vec(vec![1002, 4, 3, 4, 33]);
+        assert!(Cpu::new().execute(&mut program).is_ok());
+        assert_eq!(program.at(Address(4)), 99);
+
+        let mut program = IntcodeProgram::from_vec(vec![1101, 100, -1, 4, 0]);
+        assert!(Cpu::new().execute(&mut program).is_ok());
+        assert_eq!(program.at(Address(4)), 99);
+
+        assert_eq!(run_program_str("104,-7,4,1,99", &[]), Ok(vec![-7, -7]));
+        // Immediate parameters may hold any value, not just addresses
+        assert_eq!(run_program_str("1101,-5,-6,7,4,7,99,0", &[]), Ok(vec![-11]));
+
+        let mut program = IntcodeProgram::from_vec(vec![1301, 0, 0, 0, 99]);
+        assert_eq!(Cpu::new().execute(&mut program),
+            Err(ExecError::UnknownOpcode { opcode: 1301, address: Address(0) }));
+    }
+
+    #[test]
+    fn test_immediate_write_is_an_error() {
+        let programs = [
+            vec![10001, 0, 0, 0, 99],
+            vec![11102, 3, 4, 5, 99],
+            vec![103, 0, 99],
+            vec![11107, 1, 2, 5, 99],
+            vec![10008, 0, 0, 5, 99],
+        ];
+        for raw_program in programs.iter() {
+            let mut program = IntcodeProgram::from_vec(raw_program.clone());
+            assert_eq!(Cpu::with_inputs(&[1]).execute(&mut program),
+                Err(ExecError::ImmediateWriteTarget { address: Address(0) }), "{:?}", raw_program);
+            assert_eq!(program.raw_program, *raw_program);
+        }
+
+        let program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 10001, 0, 0, 0, 99]);
+        assert_eq!(dry_run(&program), Err(ExecError::ImmediateWriteTarget { address: Address(4) }));
+    }
+
+    #[test]
+    fn test_unknown_opcode_is_an_error() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 42, 99]);
+
+        assert_eq!(Cpu::new().execute(&mut program),
+            Err(CpuError::UnknownOpcode { opcode: 42, address: Address(4) }));
+        // The instruction before it still ran
+        assert_eq!(program.at(Address(0)), 2);
+    }
+
+    #[test]
+    fn test_negative_address_is_an_error() {
+        let mut program = IntcodeProgram::from_vec(vec![1, -1, 0, 0, 99]);
+
+        assert_eq!(Cpu::new().execute(&mut program), Err(ExecError::AtInstruction {
+            address: Address(0),
+            opcode: 1,
+            error: Box::new(ExecError::InvalidAddress { value: -1, address: Address(1) }),
+        }));
+    }
+
+    #[test]
+    fn test_out_of_bounds_is_an_error() {
+        let mut program = IntcodeProgram::from_vec(vec![1, 0, 0, 0, 2, 0, 0, MAX_MEMORY as Word, 99]);
+
+        let err = Cpu::new().execute(&mut program).unwrap_err();
+        assert_eq!(err, ExecError::AtInstruction {
+            address: Address(4),
+            opcode: 2,
+            error: Box::new(ExecError::OutOfBounds { address: Address(MAX_MEMORY) }),
+        });
+        assert_eq!(err.to_string(), "address 1048576 is out of bounds (opcode 2 at address 4)");
+    }
+
+    #[test]
+    fn test_step() {
+        let mut program = IntcodeProgram::from("3,0,4,0,99");
+        let mut cpu = Cpu::new();
+        assert_eq!(cpu.step(&mut program), Ok(StepResult::NeedInput));
+        assert_eq!(cpu.step(&mut program), Ok(StepResult::NeedInput));
+        cpu.push_input(12);
+        assert_eq!(cpu.step(&mut program), Ok(StepResult::Continue));
+        assert_eq!(cpu.step(&mut program), Ok(StepResult::Output(12)));
+        assert_eq!(cpu.step(&mut program), Ok(StepResult::Halted));
+        assert_eq!(cpu.step(&mut program), Ok(StepResult::Halted));
+        assert!(cpu.outputs().is_empty());
+    }
+
+    #[test]
+    fn test_ascii_io() {
+        // Echoes a line, then outputs 1000
+        let program = IntcodeProgram::from("3,100,4,100,1008,100,10,101,1006,101,0,104,1000,99");
+        let mut io = AsciiIo::new(Cpu::new(), program);
+        assert_eq!(io.run(), Ok(StepResult::NeedInput));
+        assert_eq!(io.text(), "");
+
+        io.feed_line("Hello, Intcode");
+        assert_eq!(io.run(), Ok(StepResult::Halted));
+        assert_eq!(io.take_text(), "Hello, Intcode\n");
+        assert_eq!(io.text(), "");
+        assert_eq!(io.values(), &[1000]);
+    }
+
+    #[test]
+    fn test_step_amplifier_ring() {
+        let src = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+            27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+        let program = IntcodeProgram::from(src);
+        let mut amps: Vec<(Cpu, IntcodeProgram)> = [9, 8, 7, 6, 5].iter()
+            .map(|phase| (Cpu::with_inputs(&[*phase]), program.clone()))
+            .collect();
+        amps[0].0.push_input(0);
+
+        // Each amplifier runs until it needs input, then hands over to the next
+        let mut current = 0;
+        let mut thrust = None;
+        loop {
+            let (cpu, memory) = &mut amps[current];
+            match cpu.step(memory).unwrap() {
+                StepResult::Continue => (),
+                StepResult::Output(signal) => {
+                    if current == 4 {
+                        thrust = Some(signal);
+                    }
+                    amps[(current + 1) % 5].0.push_input(signal);
+                },
+                StepResult::NeedInput => current = (current + 1) % 5,
+                StepResult::Halted if current == 4 => break,
+                StepResult::Halted => current += 1,
+            }
+        }
+        assert_eq!(thrust, Some(139629729));
+    }
+
+    #[test]
+    fn test_memory_grows() {
+        let mut program = IntcodeProgram::from_vec(vec![1101, 2, 3, 1000, 4, 999, 4, 1000, 99]);
+        assert_eq!(Cpu::new().execute_with_sink(&mut program, &mut Vec::new()).map(|result| result.reason),
+            Ok(HaltReason::Halted));
+        assert_eq!(program.len(), 1001);
+        assert_eq!(program.at(Address(1000)), 5);
+        assert_eq!(run_program_str("1101,2,3,1000,4,999,4,1000,99", &[]), Ok(vec![0, 5]));
+
+        // Stepping back over a write past the end shrinks memory again
+        let program = IntcodeProgram::from_vec(vec![3, 20, 99]);
+        let mut debugger = Debugger::new(Cpu::with_inputs(&[9]), program.clone(), 1);
+        assert!(debugger.step().is_ok());
+        assert_eq!(debugger.memory().len(), 21);
+        assert!(debugger.step_back());
+        assert_eq!(*debugger.memory(), program);
+    }
+
+    #[test]
+    fn test_relative_base() {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let expected: Vec<Word> = quine.split(',').map(|word| word.parse().unwrap()).collect();
+        assert_eq!(run_program_str(quine, &[]), Ok(expected));
+
+        assert_eq!(run_program_str("1102,34915192,34915192,7,4,7,99,0", &[]), Ok(vec![1219070632396864]));
+        assert_eq!(run_program_str("104,1125899906842624,99", &[]), Ok(vec![1125899906842624]));
+
+        // Writes through a relative parameter, then reads it back through a position one
+        let mut cpu = Cpu::with_inputs(&[-8]);
+        let mut program = IntcodeProgram::from("109,10,203,2,109,-8,4,12,99");
+        assert!(cpu.execute(&mut program).is_ok());
+        assert_eq!(cpu.relative_base(), 2);
+        assert_eq!(cpu.outputs(), &[Value(-8)]);
+
+        let mut program = IntcodeProgram::from_vec(vec![109, -5, 204, 2, 99]);
+        assert_eq!(Cpu::new().execute(&mut program).map_err(|err| err.root_cause().clone()),
+            Err(ExecError::InvalidRelativeAddress { base: -5, offset: 2 }));
+    }
+}
@@ -0,0 +1,26 @@
+use intcode::{run_and_read, Address, Cpu, IntcodeProgram, Memory, ProgramRunner};
+
+
+#[test]
+fn test_day_2_part_1() {
+    let mut program = IntcodeProgram::from(&String::from(include_str!("../../day_2/input")));
+    program.apply_patches(&[(Address(1), 12), (Address(2), 2)]).unwrap();
+
+    assert!(Cpu::new().execute(&mut program).is_ok());
+    assert_eq!(program.at(Address(0)), 4090689);
+}
+
+#[test]
+fn test_day_2_part_2() {
+    let program = IntcodeProgram::from(include_str!("../../day_2/input"));
+    assert_eq!(run_and_read(&program, &[(Address(1), 77), (Address(2), 33)], Address(0)), Ok(19690720));
+
+    let mut runner = ProgramRunner::new(program);
+    let answer = (0..100)
+        .flat_map(|noun| (0..100).map(move |verb| (noun, verb)))
+        .find(|&(noun, verb)| {
+            runner.run(&[(Address(1), noun), (Address(2), verb)]).unwrap();
+            runner.memory().at(Address(0)) == 19690720
+        });
+    assert_eq!(answer, Some((77, 33)));
+}